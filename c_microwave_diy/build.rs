@@ -1,6 +1,9 @@
 use cc;
 
 fn main() {
+    // cc asks to be rerun when its environment changes, which stops cargo noticing
+    // changes to our source by itself.
+    println!("cargo:rerun-if-changed=src/microwave.c");
     cc::Build::new()
         .file("src/microwave.c")
         .warnings(true)
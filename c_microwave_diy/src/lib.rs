@@ -75,11 +75,7 @@ impl MicrowaveOps for Microwave {
 #[cfg(test)]
 mod tests {
     use crate::Microwave;
-    use microwave_common::{MicrowaveOps, test_microwave};
+    use microwave_common::microwave_conformance_tests;
 
-    #[test]
-    fn it_works() {
-        let mut mw = Microwave::new();
-        assert!(test_microwave(&mut mw));
-    }
+    microwave_conformance_tests!(Microwave);
 }
@@ -1,8 +1,18 @@
 use cc;
+use std::env;
 
 fn main() {
-    cc::Build::new()
-        .file("src/microwave.c")
-        .warnings(true)
-        .compile("microwave");
+    // cc asks to be rerun when its environment changes, which stops cargo noticing
+    // changes to our source by itself.
+    println!("cargo:rerun-if-changed=src/microwave.c");
+    let mut build = cc::Build::new();
+    build.file("src/microwave.c").warnings(true);
+    // When the rust side is built with a sanitizer (IE by cargo fuzz), build the C
+    // side with it too, or we'd never see the C code's memory errors.
+    if let Ok(sanitizers) = env::var("CARGO_CFG_SANITIZE") {
+        for s in sanitizers.split(',') {
+            build.flag(format!("-fsanitize={}", s));
+        }
+    }
+    build.compile("microwave");
 }
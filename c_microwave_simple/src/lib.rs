@@ -1,85 +1,204 @@
-use microwave_common::MicrowaveOps;
+use std::ptr::NonNull;
 
-extern {
-    fn new_microwave() -> &'static mut MicrowaveC;
-    fn reset_microwave(m: &mut MicrowaveC);
-    fn tick_microwave(m: &mut MicrowaveC);
-    fn magnetron_enabled_microwave(m: &MicrowaveC) -> bool;
-    fn door_open_microwave(m: &MicrowaveC) -> bool;
-    fn time_remain_microwave(m: &MicrowaveC) -> usize;
-
-    fn action_open_door_microwave(m: &mut MicrowaveC);
-    fn action_close_door_microwave(m: &mut MicrowaveC);
-    fn action_set_time_microwave(m: &mut MicrowaveC, t: usize);
-    fn action_start_microwave(m: &mut MicrowaveC);
-    fn action_stop_microwave(m: &mut MicrowaveC);
+use microwave_common::MicrowaveOps;
+use microwave_common::snapshot::{SnapshotError, SnapshotOps, SNAPSHOT_LEN};
+
+extern "C" {
+    fn new_microwave() -> *mut MicrowaveC;
+    fn free_microwave(m: *mut MicrowaveC);
+    fn reset_microwave(m: *mut MicrowaveC);
+    fn tick_microwave(m: *mut MicrowaveC);
+    fn magnetron_enabled_microwave(m: *const MicrowaveC) -> bool;
+    fn door_open_microwave(m: *const MicrowaveC) -> bool;
+    fn time_remain_microwave(m: *const MicrowaveC) -> usize;
+
+    fn action_open_door_microwave(m: *mut MicrowaveC);
+    fn action_close_door_microwave(m: *mut MicrowaveC);
+    fn action_set_time_microwave(m: *mut MicrowaveC, t: usize);
+    fn action_start_microwave(m: *mut MicrowaveC);
+    fn action_stop_microwave(m: *mut MicrowaveC);
+
+    fn snapshot_microwave(m: *const MicrowaveC, buf: *mut u8, len: usize) -> usize;
+    fn restore_microwave(buf: *const u8, len: usize, out: *mut *mut MicrowaveC) -> i32;
 }
 
-// Basically a typed void pointer.
-#[allow(improper_ctypes)]
+// Basically a typed void pointer. The zero length array gives it a field, so it's
+// FFI safe, without letting rust make or move one.
 #[repr(C)]
-struct MicrowaveC {}
+struct MicrowaveC {
+    _private: [u8; 0],
+}
 
-struct Microwave {
-    mwave: &'static mut MicrowaveC
+// Owns what new_microwave gave us, until it's dropped.
+pub struct Microwave {
+    mwave: NonNull<MicrowaveC>,
 }
 
-impl MicrowaveOps for Microwave {
-    fn new() -> Self {
+// Nothing else has a pointer to it, so it can go to another thread with us.
+unsafe impl Send for Microwave {}
+
+impl Microwave {
+    // The C side allocates, which can fail. Rust gives up when it runs out of
+    // memory, so we do too.
+    fn owning(mwave: *mut MicrowaveC) -> Self {
         Microwave {
-            mwave: unsafe { new_microwave() }
+            mwave: NonNull::new(mwave).expect("out of memory for a C microwave"),
         }
     }
 
+    fn ptr(&self) -> *mut MicrowaveC {
+        self.mwave.as_ptr()
+    }
+}
+
+// The fuzzer found we never gave the C side's memory back.
+impl Drop for Microwave {
+    fn drop(&mut self) {
+        unsafe { free_microwave(self.ptr()) }
+    }
+}
+
+impl MicrowaveOps for Microwave {
+    fn new() -> Self {
+        Microwave::owning(unsafe { new_microwave() })
+    }
+
     fn reset(&mut self) {
-        unsafe { reset_microwave(self.mwave) }
+        unsafe { reset_microwave(self.ptr()) }
     }
 
     fn tick(&mut self) {
-        unsafe { tick_microwave(self.mwave) };
+        unsafe { tick_microwave(self.ptr()) };
     }
 
     fn magnetron_enabled(&self) -> bool {
-        unsafe { magnetron_enabled_microwave(self.mwave) }
+        unsafe { magnetron_enabled_microwave(self.ptr()) }
     }
 
     fn door_open(&self) -> bool {
-        unsafe { door_open_microwave(self.mwave) }
+        unsafe { door_open_microwave(self.ptr()) }
     }
 
     fn time_remain(&self) -> usize {
-        unsafe { time_remain_microwave(self.mwave) }
+        unsafe { time_remain_microwave(self.ptr()) }
     }
 
     fn action_open_door(&mut self) {
-        unsafe { action_open_door_microwave(self.mwave) };
+        unsafe { action_open_door_microwave(self.ptr()) };
     }
 
     fn action_close_door(&mut self) {
-        unsafe { action_close_door_microwave(self.mwave) };
+        unsafe { action_close_door_microwave(self.ptr()) };
     }
 
     fn action_set_time(&mut self, t: usize) {
-        unsafe { action_set_time_microwave(self.mwave, t) };
+        unsafe { action_set_time_microwave(self.ptr(), t) };
     }
 
     fn action_start(&mut self) {
-        unsafe { action_start_microwave(self.mwave) };
+        unsafe { action_start_microwave(self.ptr()) };
     }
 
     fn action_stop(&mut self) {
-        unsafe { action_stop_microwave(self.mwave) };
+        unsafe { action_stop_microwave(self.ptr()) };
+    }
+}
+
+impl SnapshotOps for Microwave {
+    fn snapshot(&self) -> [u8; SNAPSHOT_LEN] {
+        let mut buf = [0; SNAPSHOT_LEN];
+        let len = unsafe { snapshot_microwave(self.ptr(), buf.as_mut_ptr(), buf.len()) };
+        assert!(len == SNAPSHOT_LEN);
+        buf
+    }
+
+    fn restore(snapshot: &[u8]) -> Result<Self, SnapshotError> {
+        let mut out: *mut MicrowaveC = std::ptr::null_mut();
+        match unsafe { restore_microwave(snapshot.as_ptr(), snapshot.len(), &mut out) } {
+            0 => Ok(Microwave::owning(out)),
+            1 => Err(SnapshotError::Length),
+            2 => Err(SnapshotError::Version),
+            _ => Err(SnapshotError::Corrupt),
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::Microwave;
-    use microwave_common::{MicrowaveOps, test_microwave};
+    use microwave_common::{MicrowaveOps, microwave_conformance_tests};
+    use microwave_common::snapshot::{SnapshotError, SnapshotOps};
+
+    microwave_conformance_tests!(Microwave);
+
+    #[test]
+    fn start_after_cook() {
+        // Once the time runs out, start is a quick start again - not a 0 second cook.
+        let mut mw = Microwave::new();
+        mw.action_set_time(1);
+        mw.action_start();
+        mw.tick();
+        assert!(!mw.magnetron_enabled());
+        mw.action_start();
+        assert!(mw.magnetron_enabled());
+        assert!(mw.time_remain() == 30);
+    }
+
+    fn outputs(mw: &Microwave) -> (bool, bool, usize) {
+        (mw.door_open(), mw.magnetron_enabled(), mw.time_remain())
+    }
 
     #[test]
-    fn it_works() {
+    fn snapshot_round_trip() {
+        // Drive the microwave through every state, snapshotting as we go.
         let mut mw = Microwave::new();
-        assert!(test_microwave(&mut mw));
+        let mut seen = Vec::new();
+        mw.action_open_door();
+        seen.push(Microwave::restore(&mw.snapshot()).unwrap());
+        mw.action_set_time(25);
+        seen.push(Microwave::restore(&mw.snapshot()).unwrap());
+        mw.action_stop();
+        mw.action_close_door();
+        seen.push(Microwave::restore(&mw.snapshot()).unwrap());
+        mw.action_set_time(389);
+        seen.push(Microwave::restore(&mw.snapshot()).unwrap());
+        mw.action_start();
+        let running = Microwave::restore(&mw.snapshot()).unwrap();
+
+        assert!(outputs(&seen[0]) == (true, false, 0));
+        assert!(outputs(&seen[1]) == (true, false, 25));
+        assert!(outputs(&seen[2]) == (false, false, 0));
+        assert!(outputs(&seen[3]) == (false, false, 389));
+        // Running comes back stopped, time preserved.
+        assert!(outputs(&running) == (false, false, 389));
+    }
+
+    #[test]
+    fn snapshot_encoding() {
+        // This must match the rust implementation byte for byte, bar the thermal
+        // part at the end, which we don't have.
+        let mut mw = Microwave::new();
+        mw.action_start();
+        mw.action_start();
+        assert!(mw.snapshot() == [2, 4, 60, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        mw.action_open_door();
+        assert!(mw.snapshot() == [2, 1, 60, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        // Stopping from open time leaves the time in the struct, but not the snapshot.
+        mw.action_stop();
+        assert!(mw.snapshot() == [2, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn snapshot_rejects_bad() {
+        assert!(Microwave::restore(&[]).err() == Some(SnapshotError::Length));
+        assert!(Microwave::restore(&[2, 0, 0]).err() == Some(SnapshotError::Length));
+        assert!(Microwave::restore(&[1, 0, 0, 0, 0, 0, 0, 0, 0, 0]).err() == Some(SnapshotError::Version));
+        assert!(Microwave::restore(&[2, 5, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]).err() == Some(SnapshotError::Corrupt));
+        assert!(Microwave::restore(&[2, 0, 9, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]).err() == Some(SnapshotError::Corrupt));
+        assert!(Microwave::restore(&[2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0]).err() == Some(SnapshotError::Corrupt));
+        // An overheated snapshot from a microwave that has a thermal model is fine,
+        // we just can't do anything with that part.
+        let mw = Microwave::restore(&[2, 3, 60, 0, 0, 0, 0, 0, 0, 0, 1, 140, 0]).unwrap();
+        assert!(outputs(&mw) == (false, false, 60));
     }
 }
@@ -2,6 +2,7 @@
 #include <stdlib.h>
 #include <sys/types.h>
 #include <stdbool.h>
+#include <stdint.h>
 #include <string.h>
 
 typedef enum _microwave_state_t {
@@ -23,6 +24,11 @@ new_microwave() {
     return calloc(1, sizeof(struct microwave));
 }
 
+void
+free_microwave(struct microwave *mwave) {
+    free(mwave);
+}
+
 void
 reset_microwave(struct microwave *mwave) {
     memset(mwave, 0, sizeof(struct microwave));
@@ -81,7 +87,9 @@ tick_microwave(struct microwave *mwave) {
                 mwave->time -= 1;
             }
             if (mwave->time == 0) {
-                mwave->state = MS_CLOSEDTIMENOMTRON;
+                // Finished cooking - there is no time left, so this is CLOSEDNOTIME.
+                // CLOSEDTIMENOMTRON would let start run the magnetron with 0 time.
+                mwave->state = MS_CLOSEDNOTIME;
             }
             break;
         default:
@@ -178,5 +186,91 @@ action_stop_microwave(struct microwave *mwave) {
     }
 }
 
+/*
+ * Snapshots are the same thirteen bytes as microwave_common::snapshot - a version,
+ * the state (which is just our enum value), the time as a little endian u64, then
+ * the thermal state. We have no thermal model, so that is always zero - not
+ * overheated, and a cavity temp we never measured.
+ */
+#define SNAPSHOT_VERSION 2
+#define SNAPSHOT_LEN 13
+
+#define SNAPSHOT_OK 0
+#define SNAPSHOT_ERR_LENGTH 1
+#define SNAPSHOT_ERR_VERSION 2
+#define SNAPSHOT_ERR_CORRUPT 3
+
+size_t
+snapshot_microwave(struct microwave *mwave, uint8_t *buf, size_t len) {
+    if (len < SNAPSHOT_LEN) {
+        return 0;
+    }
+    // Stop clears the state, but not the time, so ask what the time really is.
+    uint64_t time = time_remain_microwave(mwave);
+    buf[0] = SNAPSHOT_VERSION;
+    buf[1] = (uint8_t)mwave->state;
+    for (size_t i = 0; i < 8; i++) {
+        buf[2 + i] = (uint8_t)(time >> (i * 8));
+    }
+    buf[10] = 0;
+    buf[11] = 0;
+    buf[12] = 0;
+    return SNAPSHOT_LEN;
+}
+
+int
+restore_microwave(const uint8_t *buf, size_t len, struct microwave **out) {
+    // Version first, so an old snapshot says so rather than being the wrong length.
+    if (len == 0) {
+        return SNAPSHOT_ERR_LENGTH;
+    }
+    if (buf[0] != SNAPSHOT_VERSION) {
+        return SNAPSHOT_ERR_VERSION;
+    }
+    if (len != SNAPSHOT_LEN) {
+        return SNAPSHOT_ERR_LENGTH;
+    }
+    // Anything else's thermal state is ignored, but it still has to make sense.
+    if (buf[10] > 1) {
+        return SNAPSHOT_ERR_CORRUPT;
+    }
+    uint64_t time = 0;
+    for (size_t i = 0; i < 8; i++) {
+        time |= ((uint64_t)buf[2 + i]) << (i * 8);
+    }
+    if (time > SIZE_MAX) {
+        return SNAPSHOT_ERR_CORRUPT;
+    }
 
+    microwave_state state;
+    switch(buf[1]) {
+        case MS_CLOSEDNOTIME:
+        case MS_OPENNOTIME:
+            if (time != 0) {
+                return SNAPSHOT_ERR_CORRUPT;
+            }
+            state = buf[1];
+            break;
+        case MS_OPENTIME:
+        case MS_CLOSEDTIMENOMTRON:
+            state = buf[1];
+            break;
+        case MS_CLOSEDTIMEMTRON:
+            // We were running when the power went - come back stopped.
+            state = MS_CLOSEDTIMENOMTRON;
+            break;
+        default:
+            return SNAPSHOT_ERR_CORRUPT;
+    }
 
+    struct microwave *mwave = new_microwave();
+    if (mwave == NULL) {
+        // Out of memory - the caller has to check.
+        *out = NULL;
+        return SNAPSHOT_OK;
+    }
+    mwave->state = state;
+    mwave->time = (size_t)time;
+    *out = mwave;
+    return SNAPSHOT_OK;
+}
@@ -0,0 +1,10 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use microwave_common::fuzzing::fuzz_thermal;
+use rust_microwave_simple::Microwave;
+
+fuzz_target!(|data: &[u8]| {
+    // This one has a thermal cut-out.
+    fuzz_thermal::<Microwave>(data);
+});
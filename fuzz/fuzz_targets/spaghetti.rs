@@ -0,0 +1,9 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use microwave_common::fuzzing::fuzz;
+use rust_microwave_spaghetti::Microwave;
+
+fuzz_target!(|data: &[u8]| {
+    fuzz::<Microwave>(data);
+});
@@ -0,0 +1,37 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+
+// Collect every scenario file so that adding a test doesn't mean touching any rust.
+fn main() {
+    println!("cargo:rerun-if-changed=scenarios");
+
+    let mut files: Vec<String> = fs::read_dir("scenarios")
+        .expect("scenarios directory")
+        .filter_map(|e| e.ok())
+        .map(|e| e.file_name().to_string_lossy().into_owned())
+        .filter(|n| n.ends_with(".scn"))
+        .collect();
+    // The numeric prefix decides the order, so 10_ must come after 9_.
+    files.sort_by_key(|n| {
+        let (prefix, name) = n.split_at(n.find('_').unwrap_or(0));
+        (prefix.parse::<usize>().unwrap_or(usize::MAX), name.to_string())
+    });
+
+    let mut out = String::from("pub const SCENARIOS: &[(&str, &str)] = &[\n");
+    for f in files {
+        let stem = f.trim_end_matches(".scn");
+        let name = match stem.find('_') {
+            Some(i) if stem[..i].chars().all(|c| c.is_ascii_digit()) => &stem[i + 1..],
+            _ => stem,
+        };
+        out.push_str(&format!(
+            "    ({:?}, include_str!(concat!(env!(\"CARGO_MANIFEST_DIR\"), \"/scenarios/{}\"))),\n",
+            name, f
+        ));
+    }
+    out.push_str("];\n");
+
+    let dest = Path::new(&env::var("OUT_DIR").unwrap()).join("scenarios.rs");
+    fs::write(dest, out).unwrap();
+}
@@ -0,0 +1,507 @@
+use std::fmt;
+use std::str::FromStr;
+
+use sha2::{Digest, Sha256};
+
+use crate::{Event, MicrowaveOps, Outputs};
+
+pub type Hash = [u8; 32];
+
+// The first entry chains from this.
+pub const GENESIS: Hash = [0; 32];
+
+// The things a compliance review cares about.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AuditKind {
+    DoorOpened,
+    // The magnetron came on, with this much time to go.
+    Started(usize),
+    // The magnetron went off before the time ran out.
+    Stopped,
+    // The time ran out.
+    Completed,
+    Fault(String),
+}
+
+impl fmt::Display for AuditKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AuditKind::DoorOpened => write!(f, "door_opened"),
+            AuditKind::Started(t) => write!(f, "started {}", t),
+            AuditKind::Stopped => write!(f, "stopped"),
+            AuditKind::Completed => write!(f, "completed"),
+            AuditKind::Fault(what) => write!(f, "fault {}", escape(what)),
+        }
+    }
+}
+
+// A fault is free text, but an entry has to stay on one line.
+fn escape(s: &str) -> String {
+    let mut out = String::new();
+    for c in s.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn unescape(s: &str) -> Result<String, String> {
+    let mut out = String::new();
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('\\') => out.push('\\'),
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            _ => return Err(format!("invalid escape in '{}'", s)),
+        }
+    }
+    Ok(out)
+}
+
+impl FromStr for AuditKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, rest) = match s.find(' ') {
+            Some(i) => (&s[..i], Some(&s[i + 1..])),
+            None => (s, None),
+        };
+        match (kind, rest) {
+            ("door_opened", None) => Ok(AuditKind::DoorOpened),
+            ("started", Some(t)) => t
+                .parse()
+                .map(AuditKind::Started)
+                .map_err(|_| format!("invalid time '{}'", t)),
+            ("stopped", None) => Ok(AuditKind::Stopped),
+            ("completed", None) => Ok(AuditKind::Completed),
+            ("fault", Some(what)) => unescape(what).map(AuditKind::Fault),
+            _ => Err(format!("unknown entry '{}'", s)),
+        }
+    }
+}
+
+// One line of the log. Each entry's hash covers its own contents and the hash of
+// the entry before it, so changing, removing or reordering any entry breaks every
+// hash after it. That only catches careless edits though - the hash has no key, so
+// anyone can change an entry and then recompute every hash after it, and chopping
+// entries off the end needs nothing recomputed at all. Neither can be seen from the
+// log alone. Keep head() somewhere the log's owner can't change, and check the log
+// ends with it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AuditEntry {
+    pub seq: u64,
+    // Ticks since the log was started.
+    pub tick: u64,
+    pub kind: AuditKind,
+    pub prev: Hash,
+    pub hash: Hash,
+}
+
+impl AuditEntry {
+    fn digest(seq: u64, tick: u64, kind: &AuditKind, prev: &Hash) -> Hash {
+        let mut h = Sha256::new();
+        h.update(prev);
+        h.update(format!("{} {} {}", seq, tick, kind).as_bytes());
+        h.finalize().into()
+    }
+}
+
+fn to_hex(h: &Hash) -> String {
+    h.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(s: &str) -> Option<Hash> {
+    if s.len() != 64 || !s.is_ascii() {
+        return None;
+    }
+    let mut h = [0; 32];
+    for (i, b) in h.iter_mut().enumerate() {
+        *b = u8::from_str_radix(&s[i * 2..i * 2 + 2], 16).ok()?;
+    }
+    Some(h)
+}
+
+// The export format, one entry per line:
+//
+//     <seq> <tick> <prev hash> <hash> <entry>
+//
+// IE "3 12 9f2c...e1 04ab...7d started 30". The entry goes last, as a fault can
+// have spaces in it. Newlines and backslashes in a fault are escaped, as \n and \\.
+impl fmt::Display for AuditEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {} {}",
+            self.seq,
+            self.tick,
+            to_hex(&self.prev),
+            to_hex(&self.hash),
+            self.kind
+        )
+    }
+}
+
+impl FromStr for AuditEntry {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.splitn(5, ' ');
+        let mut next = |what: &str| words.next().ok_or(format!("missing {}", what));
+        let seq = next("seq")?;
+        let seq = seq.parse().map_err(|_| format!("invalid seq '{}'", seq))?;
+        let tick = next("tick")?;
+        let tick = tick.parse().map_err(|_| format!("invalid tick '{}'", tick))?;
+        let prev = next("prev hash")?;
+        let prev = from_hex(prev).ok_or(format!("invalid hash '{}'", prev))?;
+        let hash = next("hash")?;
+        let hash = from_hex(hash).ok_or(format!("invalid hash '{}'", hash))?;
+        let kind = next("entry")?.parse()?;
+        Ok(AuditEntry {
+            seq,
+            tick,
+            kind,
+            prev,
+            hash,
+        })
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum AuditError {
+    // A line of an export couldn't be read.
+    Parse { line: usize, reason: String },
+    // The chain is broken at this entry.
+    Tampered { seq: u64, reason: String },
+}
+
+impl fmt::Display for AuditError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AuditError::Parse { line, reason } => write!(f, "line {}: {}", line, reason),
+            AuditError::Tampered { seq, reason } => write!(f, "entry {}: {}", seq, reason),
+        }
+    }
+}
+
+// Append only - there's no way to change an entry once it's written.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AuditLog {
+    entries: Vec<AuditEntry>,
+}
+
+impl AuditLog {
+    pub fn new() -> Self {
+        AuditLog::default()
+    }
+
+    pub fn entries(&self) -> &[AuditEntry] {
+        &self.entries
+    }
+
+    // The hash of the last entry. Keep this somewhere safe to detect truncation, or
+    // a chain that was edited and rehashed.
+    pub fn head(&self) -> Hash {
+        self.entries.last().map(|e| e.hash).unwrap_or(GENESIS)
+    }
+
+    fn append(&mut self, tick: u64, kind: AuditKind) {
+        let seq = self.entries.len() as u64;
+        let prev = self.head();
+        let hash = AuditEntry::digest(seq, tick, &kind, &prev);
+        self.entries.push(AuditEntry {
+            seq,
+            tick,
+            kind,
+            prev,
+            hash,
+        });
+    }
+
+    pub fn export(&self) -> String {
+        self.entries.iter().map(|e| format!("{}\n", e)).collect()
+    }
+
+    // Read an export back in. This only checks that it can be read - use verify
+    // to check nobody has changed it.
+    pub fn parse(text: &str) -> Result<Self, AuditError> {
+        let entries = text
+            .lines()
+            .enumerate()
+            .map(|(i, l)| {
+                l.parse().map_err(|reason| AuditError::Parse {
+                    line: i + 1,
+                    reason,
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(AuditLog { entries })
+    }
+
+    pub fn verify(&self) -> Result<(), AuditError> {
+        let mut prev = GENESIS;
+        for (i, e) in self.entries.iter().enumerate() {
+            let tampered = |reason: &str| {
+                Err(AuditError::Tampered {
+                    seq: e.seq,
+                    reason: reason.to_string(),
+                })
+            };
+            if e.seq != i as u64 {
+                return tampered("out of sequence");
+            }
+            if e.prev != prev {
+                return tampered("doesn't follow the entry before it");
+            }
+            if e.hash != AuditEntry::digest(e.seq, e.tick, &e.kind, &e.prev) {
+                return tampered("hash doesn't match its contents");
+            }
+            prev = e.hash;
+        }
+        Ok(())
+    }
+}
+
+// Check an exported log, IE one handed to us for review.
+pub fn verify_export(text: &str) -> Result<AuditLog, AuditError> {
+    let log = AuditLog::parse(text)?;
+    log.verify()?;
+    Ok(log)
+}
+
+// Wraps any microwave and logs what it does. We only look at the outputs, so it
+// doesn't matter how (or how well) the microwave is written.
+pub struct Audited<T: MicrowaveOps> {
+    inner: T,
+    log: AuditLog,
+    ticks: u64,
+}
+
+impl<T: MicrowaveOps> Audited<T> {
+    pub fn wrap(inner: T) -> Self {
+        Audited {
+            inner,
+            log: AuditLog::new(),
+            ticks: 0,
+        }
+    }
+
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    pub fn log(&self) -> &AuditLog {
+        &self.log
+    }
+
+    // For faults we can't see from the outputs, IE a welded relay.
+    pub fn record_fault(&mut self, what: &str) {
+        self.log.append(self.ticks, AuditKind::Fault(what.to_string()));
+    }
+
+    fn step(&mut self, event: Event) {
+        let before = Outputs::of(&self.inner);
+        event.apply(&mut self.inner);
+        if event == Event::Tick {
+            self.ticks += 1;
+        }
+        let after = Outputs::of(&self.inner);
+
+        if after.door_open && !before.door_open {
+            self.log.append(self.ticks, AuditKind::DoorOpened);
+        }
+        if after.magnetron && !before.magnetron {
+            self.log.append(self.ticks, AuditKind::Started(after.time_remain));
+        }
+        if before.magnetron && !after.magnetron {
+            let kind = if event == Event::Tick && after.time_remain == 0 {
+                AuditKind::Completed
+            } else {
+                AuditKind::Stopped
+            };
+            self.log.append(self.ticks, kind);
+        }
+        // Once when it happens, not on every step until it stops.
+        let unsafe_combo = |o: &Outputs| o.door_open && o.magnetron;
+        if unsafe_combo(&after) && !unsafe_combo(&before) {
+            self.record_fault("magnetron on with the door open");
+        }
+    }
+}
+
+impl<T: MicrowaveOps> MicrowaveOps for Audited<T> {
+    fn new() -> Self {
+        Audited::wrap(T::new())
+    }
+
+    fn reset(&mut self) {
+        self.step(Event::Reset);
+    }
+
+    fn tick(&mut self) {
+        self.step(Event::Tick);
+    }
+
+    fn magnetron_enabled(&self) -> bool {
+        self.inner.magnetron_enabled()
+    }
+
+    fn door_open(&self) -> bool {
+        self.inner.door_open()
+    }
+
+    fn time_remain(&self) -> usize {
+        self.inner.time_remain()
+    }
+
+    fn action_open_door(&mut self) {
+        self.step(Event::OpenDoor);
+    }
+
+    fn action_close_door(&mut self) {
+        self.step(Event::CloseDoor);
+    }
+
+    fn action_set_time(&mut self, t: usize) {
+        self.step(Event::SetTime(t));
+    }
+
+    fn action_start(&mut self) {
+        self.step(Event::Start);
+    }
+
+    fn action_stop(&mut self) {
+        self.step(Event::Stop);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::audit::{verify_export, AuditEntry, AuditError, AuditKind, AuditLog, Audited, GENESIS};
+    use crate::faulty::{Faults, Faulty};
+    use crate::model::Model;
+    use crate::MicrowaveOps;
+
+    fn cook() -> Audited<Model> {
+        let mut mw = Audited::<Model>::new();
+        mw.action_set_time(2);
+        mw.action_start();
+        mw.tick();
+        mw.action_open_door();
+        mw.action_close_door();
+        mw.action_start();
+        mw.tick();
+        mw.record_fault("relay welded");
+        mw
+    }
+
+    #[test]
+    fn logs() {
+        let mw = cook();
+        let kinds: Vec<(u64, AuditKind)> = mw.log().entries().iter().map(|e| (e.tick, e.kind.clone())).collect();
+        assert!(
+            kinds
+                == vec![
+                    (0, AuditKind::Started(2)),
+                    (1, AuditKind::DoorOpened),
+                    (1, AuditKind::Stopped),
+                    (1, AuditKind::Started(1)),
+                    (2, AuditKind::Completed),
+                    (2, AuditKind::Fault("relay welded".to_string())),
+                ]
+        );
+        assert!(mw.log().entries()[0].prev == GENESIS);
+        assert!(mw.log().head() == mw.log().entries()[5].hash);
+        assert!(mw.log().verify().is_ok());
+    }
+
+    #[test]
+    fn export() {
+        let mw = cook();
+        let text = mw.log().export();
+        assert!(text.lines().count() == 6);
+        assert!(text.lines().nth(5).unwrap().ends_with(" fault relay welded"));
+        assert!(verify_export(&text).unwrap() == *mw.log());
+
+        // A fault can't add lines of its own.
+        let mut mw = Audited::<Model>::new();
+        mw.record_fault("relay welded\n0 0 forged\\n");
+        let text = mw.log().export();
+        assert!(text.lines().count() == 1);
+        assert!(text.ends_with(" fault relay welded\\n0 0 forged\\\\n\n"));
+        assert!(verify_export(&text).unwrap() == *mw.log());
+        assert!("fault bad \\x".parse::<AuditKind>().is_err());
+    }
+
+    #[test]
+    fn fault_once() {
+        let mut mw = Audited::wrap(Faulty::with(Faults {
+            ignores_door: true,
+            ..Faults::default()
+        }));
+        mw.action_start();
+        mw.action_open_door();
+        mw.tick();
+        mw.tick();
+        mw.action_close_door();
+        mw.action_open_door();
+        let faults = mw
+            .log()
+            .entries()
+            .iter()
+            .filter(|e| matches!(e.kind, AuditKind::Fault(_)))
+            .count();
+        assert!(faults == 2);
+    }
+
+    #[test]
+    fn tampering() {
+        let text = cook().log().export();
+        let lines: Vec<&str> = text.lines().collect();
+
+        // Say it stopped, rather than that the door was opened.
+        let edited = text.replace("door_opened", "stopped");
+        assert!(
+            verify_export(&edited)
+                == Err(AuditError::Tampered {
+                    seq: 1,
+                    reason: "hash doesn't match its contents".to_string()
+                })
+        );
+
+        // Lose the stop.
+        let mut removed = lines.clone();
+        removed.remove(2);
+        let removed = removed.join("\n");
+        assert!(
+            verify_export(&removed)
+                == Err(AuditError::Tampered {
+                    seq: 3,
+                    reason: "out of sequence".to_string()
+                })
+        );
+
+        // Change the time, and fix up the hash to match - the next entry notices.
+        let mut rehashed = AuditLog::parse(&text).unwrap();
+        rehashed.entries[0].tick = 5;
+        let e = &rehashed.entries[0];
+        rehashed.entries[0].hash = AuditEntry::digest(e.seq, e.tick, &e.kind, &e.prev);
+        assert!(
+            rehashed.verify()
+                == Err(AuditError::Tampered {
+                    seq: 1,
+                    reason: "doesn't follow the entry before it".to_string()
+                })
+        );
+
+        assert!(verify_export("0 0 nope").unwrap_err().to_string() == "line 1: invalid hash 'nope'");
+    }
+}
@@ -0,0 +1,102 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// How long a virtual clock lets a waiter sleep before checking again.
+const VIRTUAL_POLL: Duration = Duration::from_millis(1);
+
+// Where time comes from. Anything that turns time into ticks should be generic over
+// this, so that tests can skip ten minutes without waiting ten minutes.
+pub trait Clock {
+    // Time since some fixed point. This must never go backwards.
+    fn now(&self) -> Duration;
+
+    // How long to really sleep, waiting for deadline to arrive.
+    fn wait_for(&self, deadline: Duration) -> Duration {
+        deadline.checked_sub(self.now()).unwrap_or(Duration::from_secs(0))
+    }
+}
+
+// The real, monotonic clock.
+pub struct SystemClock {
+    epoch: Instant,
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        SystemClock::new()
+    }
+}
+
+impl SystemClock {
+    pub fn new() -> Self {
+        SystemClock {
+            epoch: Instant::now(),
+        }
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.epoch.elapsed()
+    }
+}
+
+// A clock that only moves when it's told to. Clones share the same time, so keep
+// one to advance while something else owns the other.
+#[derive(Clone, Default)]
+pub struct VirtualClock {
+    now: Arc<Mutex<Duration>>,
+}
+
+impl VirtualClock {
+    pub fn new() -> Self {
+        VirtualClock::default()
+    }
+
+    pub fn advance(&self, d: Duration) {
+        let mut now = self.now.lock().unwrap();
+        *now += d;
+    }
+}
+
+impl Clock for VirtualClock {
+    fn now(&self) -> Duration {
+        *self.now.lock().unwrap()
+    }
+
+    // Time won't pass by itself, so there is no point sleeping until the deadline.
+    // Check back soon in case someone has advanced us.
+    fn wait_for(&self, deadline: Duration) -> Duration {
+        if deadline <= self.now() {
+            Duration::from_secs(0)
+        } else {
+            VIRTUAL_POLL
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::clock::{Clock, SystemClock, VirtualClock};
+
+    #[test]
+    fn virtual_clock() {
+        let c = VirtualClock::new();
+        let c2 = c.clone();
+        assert!(c.now() == Duration::from_secs(0));
+        c2.advance(Duration::from_secs(600));
+        assert!(c.now() == Duration::from_secs(600));
+        assert!(c.wait_for(Duration::from_secs(1)) == Duration::from_secs(0));
+        assert!(c.wait_for(Duration::from_secs(601)) < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn system_clock() {
+        let c = SystemClock::new();
+        let a = c.now();
+        assert!(c.now() >= a);
+        assert!(c.wait_for(a + Duration::from_secs(10)) <= Duration::from_secs(10));
+    }
+}
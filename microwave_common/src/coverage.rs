@@ -0,0 +1,196 @@
+use std::fmt;
+
+use crate::model::{Input, Model, State};
+use crate::report::ConformanceReport;
+use crate::{test_microwave, Event, MicrowaveOps};
+
+// How many times each (state, input) cell of the README table was exercised.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Coverage {
+    counts: [[usize; 5]; 6],
+}
+
+impl Coverage {
+    pub fn new() -> Self {
+        Coverage::default()
+    }
+
+    pub fn record(&mut self, state: State, input: Input) {
+        self.counts[input.index()][state.index()] += 1;
+    }
+
+    pub fn count(&self, state: State, input: Input) -> usize {
+        self.counts[input.index()][state.index()]
+    }
+
+    // Every cell that was never exercised, row by row.
+    pub fn untested(&self) -> Vec<(State, Input)> {
+        Input::ALL
+            .iter()
+            .flat_map(|i| State::ALL.iter().map(move |s| (*s, *i)))
+            .filter(|(s, i)| self.count(*s, *i) == 0)
+            .collect()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.untested().is_empty()
+    }
+}
+
+// Laid out like the table in the README, with the number of times each cell was
+// hit. Untested cells are marked with !!.
+impl fmt::Display for Coverage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "|            |")?;
+        for s in State::ALL.iter() {
+            write!(f, " {:<21} |", s.name())?;
+        }
+        writeln!(f)?;
+        for i in Input::ALL.iter() {
+            write!(f, "| {:<10} |", i.name())?;
+            for s in State::ALL.iter() {
+                match self.count(*s, *i) {
+                    0 => write!(f, " {:<21} |", "!!")?,
+                    n => write!(f, " {:<21} |", n)?,
+                }
+            }
+            writeln!(f)?;
+        }
+        let total = State::ALL.len() * Input::ALL.len();
+        writeln!(f, "{} of {} cells covered", total - self.untested().len(), total)
+    }
+}
+
+// Wraps a microwave, and uses the reference model to work out which state we were
+// in when each input arrived. The model is used rather than the outputs of the
+// wrapped microwave, as that is what is being tested and may well be wrong.
+pub struct Covered<T: MicrowaveOps> {
+    inner: T,
+    model: Model,
+    coverage: Coverage,
+}
+
+impl<T: MicrowaveOps> Covered<T> {
+    pub fn wrap(inner: T) -> Self {
+        Covered {
+            inner,
+            model: Model::new(),
+            coverage: Coverage::new(),
+        }
+    }
+
+    pub fn coverage(&self) -> &Coverage {
+        &self.coverage
+    }
+
+    pub fn into_inner(self) -> (T, Coverage) {
+        (self.inner, self.coverage)
+    }
+
+    fn step(&mut self, event: Event) {
+        if let Some(input) = Input::of(event) {
+            self.coverage.record(self.model.state(), input);
+        }
+        event.apply(&mut self.model);
+        event.apply(&mut self.inner);
+    }
+}
+
+impl<T: MicrowaveOps> MicrowaveOps for Covered<T> {
+    fn new() -> Self {
+        Covered::wrap(T::new())
+    }
+
+    fn reset(&mut self) {
+        self.step(Event::Reset);
+    }
+
+    fn tick(&mut self) {
+        self.step(Event::Tick);
+    }
+
+    fn magnetron_enabled(&self) -> bool {
+        self.inner.magnetron_enabled()
+    }
+
+    fn door_open(&self) -> bool {
+        self.inner.door_open()
+    }
+
+    fn time_remain(&self) -> usize {
+        self.inner.time_remain()
+    }
+
+    fn action_open_door(&mut self) {
+        self.step(Event::OpenDoor);
+    }
+
+    fn action_close_door(&mut self) {
+        self.step(Event::CloseDoor);
+    }
+
+    fn action_set_time(&mut self, t: usize) {
+        self.step(Event::SetTime(t));
+    }
+
+    fn action_start(&mut self) {
+        self.step(Event::Start);
+    }
+
+    fn action_stop(&mut self) {
+        self.step(Event::Stop);
+    }
+}
+
+// Run the conformance suite, and measure which cells of the table it hit.
+pub fn test_coverage<T: MicrowaveOps>(mw: T) -> (ConformanceReport, Coverage) {
+    let mut covered = Covered::wrap(mw);
+    let report = test_microwave(&mut covered);
+    let (_, coverage) = covered.into_inner();
+    (report, coverage)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::coverage::{test_coverage, Coverage, Covered};
+    use crate::model::{Input, Model, State};
+    use crate::MicrowaveOps;
+
+    #[test]
+    fn suite_covers_table() {
+        let (report, coverage) = test_coverage(Model::new());
+        println!("{}", coverage);
+        assert!(report.passed());
+        assert!(coverage.untested() == vec![]);
+        assert!(coverage.is_complete());
+    }
+
+    #[test]
+    fn classify() {
+        let mut mw = Covered::wrap(Model::new());
+        mw.action_open_door();
+        mw.action_start();
+        mw.action_close_door();
+        mw.action_start();
+        mw.tick();
+        let c = mw.coverage();
+        assert!(c.count(State::ClosedNoTimeNoMtron, Input::OpenDoor) == 1);
+        assert!(c.count(State::OpenNoTime, Input::Start) == 1);
+        assert!(c.count(State::OpenNoTime, Input::CloseDoor) == 1);
+        assert!(c.count(State::ClosedNoTimeNoMtron, Input::Start) == 1);
+        assert!(c.count(State::ClosedTimeMtron, Input::OneSecond) == 1);
+        assert!(c.untested().len() == 25);
+    }
+
+    #[test]
+    fn matrix() {
+        let mut c = Coverage::new();
+        c.record(State::OpenTime, Input::Stop);
+        c.record(State::OpenTime, Input::Stop);
+        let text = c.to_string();
+        let lines: Vec<&str> = text.lines().collect();
+        assert!(lines[0].starts_with("|            | OPEN_NOTIME           | OPEN_TIME "));
+        assert!(lines[4] == "| stop       | !!                    | 2                     | !!                    | !!                    | !!                    |");
+        assert!(lines[7] == "1 of 30 cells covered");
+    }
+}
@@ -0,0 +1,189 @@
+use core::fmt;
+
+use crate::keypad::Keypad;
+use crate::MicrowaveOps;
+
+// Each digit is a bitmask of its seven segments:
+//
+//      aaa
+//     f   b
+//      ggg
+//     e   c
+//      ddd
+//
+// Where a is bit 0 through to g at bit 6.
+pub const SEG_BLANK: u8 = 0x00;
+pub const SEG_DIGITS: [u8; 10] = [0x3F, 0x06, 0x5B, 0x4F, 0x66, 0x6D, 0x7D, 0x07, 0x7F, 0x6F];
+pub const SEG_D: u8 = 0x5E;
+pub const SEG_O: u8 = 0x5C;
+pub const SEG_R: u8 = 0x50;
+
+// The most a four digit display can show.
+const MAX_MINUTES: usize = 99;
+const MAX_SECONDS: usize = 59;
+const MAX_DIGIT_PAIR: usize = 99;
+
+// One frame of a four digit seven segment display, with a colon between the
+// second and third digits.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Frame {
+    pub digits: [u8; 4],
+    pub colon: bool,
+}
+
+impl Frame {
+    pub fn blank() -> Self {
+        Frame {
+            digits: [SEG_BLANK; 4],
+            colon: false,
+        }
+    }
+
+    pub fn door() -> Self {
+        Frame {
+            digits: [SEG_D, SEG_O, SEG_O, SEG_R],
+            colon: false,
+        }
+    }
+
+    // MM:SS. The leading zero of the minutes is blanked, as a microwave does. The
+    // seconds are shown as given, even past 59 - a half typed 1:75 has to look like
+    // what was typed.
+    pub fn minutes_seconds(minutes: usize, seconds: usize) -> Self {
+        let (minutes, seconds) = if minutes > MAX_MINUTES {
+            (MAX_MINUTES, MAX_SECONDS)
+        } else {
+            (minutes, seconds.min(MAX_DIGIT_PAIR))
+        };
+        Frame {
+            digits: [
+                if minutes >= 10 { SEG_DIGITS[minutes / 10] } else { SEG_BLANK },
+                SEG_DIGITS[minutes % 10],
+                SEG_DIGITS[seconds / 10],
+                SEG_DIGITS[seconds % 10],
+            ],
+            colon: true,
+        }
+    }
+
+    pub fn time(seconds: usize) -> Self {
+        Frame::minutes_seconds(seconds / 60, seconds % 60)
+    }
+
+    // What the display shows for a microwave. Like everything else, this is just a
+    // function of the outputs:
+    //
+    // * door open - "door"
+    // * a time set, running or not - the time
+    // * no time - blank
+    pub fn render<T: MicrowaveOps>(mw: &T) -> Self {
+        if mw.door_open() {
+            Frame::door()
+        } else if mw.time_remain() > 0 {
+            Frame::time(mw.time_remain())
+        } else {
+            Frame::blank()
+        }
+    }
+
+    // While a time is being typed in, the display shows that instead.
+    pub fn render_with_keypad<T: MicrowaveOps>(mw: &T, keypad: &Keypad) -> Self {
+        match keypad.entry() {
+            Some((m, s)) => Frame::minutes_seconds(m, s),
+            None => Frame::render(mw),
+        }
+    }
+}
+
+fn segment_char(seg: u8) -> char {
+    match seg {
+        SEG_BLANK => ' ',
+        SEG_D => 'd',
+        SEG_O => 'o',
+        SEG_R => 'r',
+        s => match SEG_DIGITS.iter().position(|d| *d == s) {
+            Some(n) => (b'0' + n as u8) as char,
+            None => '?',
+        },
+    }
+}
+
+// Read the frame back as text, IE " 1:30", so tests can see what a person would.
+impl fmt::Display for Frame {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}{}{}{}{}",
+            segment_char(self.digits[0]),
+            segment_char(self.digits[1]),
+            if self.colon { ':' } else { ' ' },
+            segment_char(self.digits[2]),
+            segment_char(self.digits[3]),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::display::Frame;
+    use crate::keypad::{Key, Keypad};
+    use crate::model::Model;
+    use crate::MicrowaveOps;
+
+    #[test]
+    fn segments() {
+        assert!(Frame::time(90).digits == [0x00, 0x06, 0x4F, 0x3F]);
+        assert!(Frame::time(90).colon);
+        assert!(Frame::door().digits == [0x5E, 0x5C, 0x5C, 0x50]);
+        assert!(!Frame::door().colon);
+    }
+
+    #[test]
+    fn text() {
+        assert!(Frame::time(90).to_string() == " 1:30");
+        assert!(Frame::time(45).to_string() == " 0:45");
+        assert!(Frame::time(389).to_string() == " 6:29");
+        assert!(Frame::time(60 * 25).to_string() == "25:00");
+        // Too long to show.
+        assert!(Frame::time(100 * 60).to_string() == "99:59");
+        assert!(Frame::blank().to_string() == "     ");
+        assert!(Frame::door().to_string() == "do or");
+    }
+
+    #[test]
+    fn render() {
+        let mut mw = Model::new();
+        mw.action_set_time(59);
+        mw.action_start();
+        assert!(Frame::render(&mw).to_string() == " 0:59");
+        mw.action_stop();
+        assert!(Frame::render(&mw).to_string() == " 0:59");
+        mw.action_open_door();
+        assert!(Frame::render(&mw) == Frame::door());
+        mw.action_close_door();
+        mw.action_stop();
+        assert!(Frame::render(&mw) == Frame::blank());
+    }
+
+    #[test]
+    fn render_keypad() {
+        let mut mw = Model::new();
+        let mut kp = Keypad::new();
+        kp.press(Key::Digit(1), &mut mw).unwrap();
+        kp.press(Key::Digit(3), &mut mw).unwrap();
+        assert!(Frame::render_with_keypad(&mw, &kp).to_string() == " 0:13");
+        kp.press(Key::Digit(0), &mut mw).unwrap();
+        assert!(Frame::render_with_keypad(&mw, &kp).to_string() == " 1:30");
+        // Once committed, we show the microwave's time.
+        kp.press(Key::TimeCook, &mut mw).unwrap();
+        assert!(mw.time_remain() == 90);
+        assert!(Frame::render_with_keypad(&mw, &kp).to_string() == " 1:30");
+
+        // Not a valid time, but it's what was typed.
+        for d in [1, 7, 5] {
+            kp.press(Key::Digit(d), &mut mw).unwrap();
+        }
+        assert!(Frame::render_with_keypad(&mw, &kp).to_string() == " 1:75");
+        assert!(Frame::minutes_seconds(0, 99).to_string() == " 0:99");
+    }
+}
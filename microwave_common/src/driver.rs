@@ -0,0 +1,220 @@
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::time::Duration;
+
+use crate::clock::{Clock, SystemClock};
+use crate::{Event, MicrowaveOps, TraceRecord};
+
+// Our microwaves count in seconds.
+pub const TICK_PERIOD: Duration = Duration::from_secs(1);
+
+// Something has to call tick() once a second, and that's this. The driver owns a
+// microwave, takes events from a channel, and ticks from a clock - the real one,
+// unless you give it another.
+//
+// Ticks are counted from when the driver was created, not from the last tick, so
+// we never drift. If we fall behind (IE the machine was suspended), every missed
+// tick is still issued, in order, before any newer event is applied.
+pub struct Driver<T: MicrowaveOps, C: Clock = SystemClock> {
+    mw: T,
+    events: Receiver<Event>,
+    clock: C,
+    period: Duration,
+    // When the next tick is due. This moves on a whole period at a time, so
+    // it's always the start plus a whole number of periods.
+    deadline: Duration,
+    ticks: u64,
+}
+
+impl<T: MicrowaveOps> Driver<T, SystemClock> {
+    pub fn new(mw: T, events: Receiver<Event>) -> Self {
+        Driver::with_clock(mw, events, SystemClock::new())
+    }
+}
+
+impl<T: MicrowaveOps, C: Clock> Driver<T, C> {
+    pub fn with_clock(mw: T, events: Receiver<Event>, clock: C) -> Self {
+        let deadline = clock.now() + TICK_PERIOD;
+        Driver {
+            mw,
+            events,
+            clock,
+            period: TICK_PERIOD,
+            deadline,
+            ticks: 0,
+        }
+    }
+
+    // Tick faster (or slower) than real time. Restarts the tick count. A zero
+    // period would mean every tick is always due, so that panics.
+    pub fn with_period(mut self, period: Duration) -> Self {
+        assert!(period > Duration::from_secs(0), "the tick period can't be zero");
+        self.period = period;
+        self.deadline = self.clock.now() + period;
+        self.ticks = 0;
+        self
+    }
+
+    pub fn microwave(&self) -> &T {
+        &self.mw
+    }
+
+    pub fn into_inner(self) -> T {
+        self.mw
+    }
+
+    // How many ticks have been issued.
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    fn catch_up(&mut self, observe: &mut dyn FnMut(&TraceRecord)) {
+        let now = self.clock.now();
+        while self.deadline <= now {
+            self.deadline += self.period;
+            self.ticks += 1;
+            observe(&TraceRecord::step(&mut self.mw, Event::Tick));
+        }
+    }
+
+    // Issue any ticks that are due, then apply every event waiting in the channel.
+    // Returns what happened, in order. This never blocks.
+    pub fn poll(&mut self) -> Vec<TraceRecord> {
+        let mut trace = Vec::new();
+        self.catch_up(&mut |r| trace.push(*r));
+        while let Ok(e) = self.events.try_recv() {
+            trace.push(TraceRecord::step(&mut self.mw, e));
+        }
+        trace
+    }
+
+    // Run the microwave until every sender has hung up, then give it back. Every
+    // step is passed to observe as it happens.
+    pub fn run<F: FnMut(&TraceRecord)>(mut self, mut observe: F) -> T {
+        loop {
+            let timeout = self.clock.wait_for(self.deadline);
+            let event = match self.events.recv_timeout(timeout) {
+                Ok(e) => Some(e),
+                Err(RecvTimeoutError::Timeout) => None,
+                Err(RecvTimeoutError::Disconnected) => break,
+            };
+            // However long we waited, first account for the time that passed.
+            self.catch_up(&mut observe);
+            if let Some(e) = event {
+                observe(&TraceRecord::step(&mut self.mw, e));
+            }
+        }
+        self.mw
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc::channel;
+    use std::thread;
+    use std::time::Duration;
+
+    use crate::clock::VirtualClock;
+    use crate::driver::Driver;
+    use crate::model::Model;
+    use crate::{Event, MicrowaveOps};
+
+    #[test]
+    fn poll_catches_up() {
+        let clock = VirtualClock::new();
+        let (tx, rx) = channel();
+        let mut d = Driver::with_clock(Model::new(), rx, clock.clone());
+        tx.send(Event::SetTime(900)).unwrap();
+        tx.send(Event::Start).unwrap();
+        let trace = d.poll();
+        assert!(trace.len() == 2);
+        assert!(d.microwave().magnetron_enabled());
+
+        // Nothing happens until time passes.
+        assert!(d.poll().is_empty());
+        clock.advance(Duration::from_millis(999));
+        assert!(d.poll().is_empty());
+        clock.advance(Duration::from_millis(1));
+        assert!(d.poll().len() == 1);
+        assert!(d.microwave().time_remain() == 899);
+
+        // Ten minutes, instantly. Every missed tick is issued before the stop
+        // that arrived while we were "away".
+        clock.advance(Duration::from_secs(600));
+        tx.send(Event::Stop).unwrap();
+        let trace = d.poll();
+        assert!(trace.len() == 601);
+        assert!(trace[..600].iter().all(|r| r.event == Event::Tick));
+        assert!(trace[600].event == Event::Stop);
+        assert!(d.ticks() == 601);
+        assert!(d.microwave().time_remain() == 299);
+        assert!(!d.microwave().magnetron_enabled());
+    }
+
+    #[test]
+    fn period() {
+        let clock = VirtualClock::new();
+        let (_tx, rx) = channel();
+        let mut d = Driver::with_clock(Model::new(), rx, clock.clone())
+            .with_period(Duration::from_millis(100));
+        clock.advance(Duration::from_millis(350));
+        assert!(d.poll().len() == 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "the tick period can't be zero")]
+    fn zero_period() {
+        let (_tx, rx) = channel::<Event>();
+        let _ = Driver::with_clock(Model::new(), rx, VirtualClock::new()).with_period(Duration::from_secs(0));
+    }
+
+    #[test]
+    fn run_virtual() {
+        let clock = VirtualClock::new();
+        let (tx, rx) = channel();
+        let (seen_tx, seen) = channel();
+        let d = Driver::with_clock(Model::new(), rx, clock.clone());
+        let h = thread::spawn(move || d.run(|r| seen_tx.send(*r).unwrap()));
+
+        tx.send(Event::SetTime(60)).unwrap();
+        tx.send(Event::Start).unwrap();
+        // Wait for the driver to have started us before time moves.
+        while seen.recv().unwrap().event != Event::Start {}
+        clock.advance(Duration::from_secs(45));
+        for _ in 0..45 {
+            assert!(seen.recv().unwrap().event == Event::Tick);
+        }
+        drop(tx);
+
+        let mw = h.join().unwrap();
+        assert!(mw.time_remain() == 15);
+        assert!(seen.try_recv().is_err());
+    }
+
+    #[test]
+    fn run_until_hangup() {
+        let clock = VirtualClock::new();
+        let (tx, rx) = channel();
+        let (seen_tx, seen) = channel();
+        let d = Driver::with_clock(Model::new(), rx, clock.clone()).with_period(Duration::from_millis(5));
+        let h = thread::spawn(move || d.run(|r| seen_tx.send(*r).unwrap()));
+
+        tx.send(Event::SetTime(1000)).unwrap();
+        tx.send(Event::Start).unwrap();
+        // Events come out in the order they went in, and no time has passed yet.
+        assert!(seen.recv().unwrap().event == Event::SetTime(1000));
+        assert!(seen.recv().unwrap().event == Event::Start);
+        clock.advance(Duration::from_millis(30));
+        for _ in 0..6 {
+            assert!(seen.recv().unwrap().event == Event::Tick);
+        }
+        tx.send(Event::Stop).unwrap();
+        drop(tx);
+
+        // Hanging up ends the run, and hands back the microwave as it was left.
+        let mw = h.join().unwrap();
+        assert!(!mw.magnetron_enabled());
+        assert!(mw.time_remain() == 994);
+        let rest: Vec<Event> = seen.iter().map(|r| r.event).collect();
+        assert!(rest == vec![Event::Stop]);
+    }
+}
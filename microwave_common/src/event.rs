@@ -0,0 +1,166 @@
+use core::fmt;
+#[cfg(feature = "std")]
+use std::str::FromStr;
+
+use crate::MicrowaveOps;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+// Every input a microwave can receive, as a value. Handy when you want to send
+// inputs somewhere, or write them down.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Event {
+    Reset,
+    OpenDoor,
+    CloseDoor,
+    SetTime(usize),
+    Start,
+    Stop,
+    Tick,
+}
+
+impl Event {
+    pub fn apply<T: MicrowaveOps>(&self, mw: &mut T) {
+        match *self {
+            Event::Reset => mw.reset(),
+            Event::OpenDoor => mw.action_open_door(),
+            Event::CloseDoor => mw.action_close_door(),
+            Event::SetTime(t) => mw.action_set_time(t),
+            Event::Start => mw.action_start(),
+            Event::Stop => mw.action_stop(),
+            Event::Tick => mw.tick(),
+        }
+    }
+}
+
+// Events are written the same way as the MicrowaveOps actions, IE "set_time 30".
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Event::Reset => write!(f, "reset"),
+            Event::OpenDoor => write!(f, "open_door"),
+            Event::CloseDoor => write!(f, "close_door"),
+            Event::SetTime(t) => write!(f, "set_time {}", t),
+            Event::Start => write!(f, "start"),
+            Event::Stop => write!(f, "stop"),
+            Event::Tick => write!(f, "tick"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl FromStr for Event {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let words: Vec<&str> = s.split_whitespace().collect();
+        match words.as_slice() {
+            ["reset"] => Ok(Event::Reset),
+            ["open_door"] => Ok(Event::OpenDoor),
+            ["close_door"] => Ok(Event::CloseDoor),
+            ["set_time", t] => t
+                .parse()
+                .map(Event::SetTime)
+                .map_err(|_| format!("invalid time '{}'", t)),
+            ["start"] => Ok(Event::Start),
+            ["stop"] => Ok(Event::Stop),
+            ["tick"] => Ok(Event::Tick),
+            _ => Err(format!("unknown event '{}'", s.trim())),
+        }
+    }
+}
+
+// The three outputs of a microwave at some instant.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Outputs {
+    pub door_open: bool,
+    pub magnetron: bool,
+    pub time_remain: usize,
+}
+
+impl Outputs {
+    pub fn of<T: MicrowaveOps>(mw: &T) -> Self {
+        Outputs {
+            door_open: mw.door_open(),
+            magnetron: mw.magnetron_enabled(),
+            time_remain: mw.time_remain(),
+        }
+    }
+}
+
+// The same way they are written in a scenario, IE "closed on 30".
+impl fmt::Display for Outputs {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {}",
+            if self.door_open { "open" } else { "closed" },
+            if self.magnetron { "on" } else { "off" },
+            self.time_remain
+        )
+    }
+}
+
+// An event, and the outputs that resulted from it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TraceRecord {
+    pub event: Event,
+    pub outputs: Outputs,
+}
+
+impl TraceRecord {
+    pub fn step<T: MicrowaveOps>(mw: &mut T, event: Event) -> Self {
+        event.apply(mw);
+        TraceRecord {
+            event,
+            outputs: Outputs::of(mw),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use crate::event::{Event, Outputs, TraceRecord};
+
+    #[test]
+    fn event_json() {
+        let events = [
+            Event::Reset,
+            Event::OpenDoor,
+            Event::CloseDoor,
+            Event::SetTime(90),
+            Event::Start,
+            Event::Stop,
+            Event::Tick,
+        ];
+        for e in events.iter() {
+            let json = serde_json::to_string(e).unwrap();
+            assert!(serde_json::from_str::<Event>(&json).unwrap() == *e);
+        }
+        assert!(serde_json::to_string(&Event::Start).unwrap() == "\"Start\"");
+        assert!(serde_json::to_string(&Event::SetTime(90)).unwrap() == "{\"SetTime\":90}");
+    }
+
+    #[test]
+    fn trace_json() {
+        let trace = vec![
+            TraceRecord {
+                event: Event::Start,
+                outputs: Outputs { door_open: false, magnetron: true, time_remain: 30 },
+            },
+            TraceRecord {
+                event: Event::OpenDoor,
+                outputs: Outputs { door_open: true, magnetron: false, time_remain: 30 },
+            },
+        ];
+        let json = serde_json::to_string(&trace).unwrap();
+        assert!(serde_json::from_str::<Vec<TraceRecord>>(&json).unwrap() == trace);
+        assert!(json.starts_with(
+            "[{\"event\":\"Start\",\"outputs\":{\"door_open\":false,\"magnetron\":true,\"time_remain\":30}}"
+        ));
+    }
+}
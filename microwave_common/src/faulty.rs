@@ -0,0 +1,160 @@
+use crate::MicrowaveOps;
+
+// Bugs that can be switched on, to check that whatever is meant to catch a broken
+// microwave actually does.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Faults {
+    // Opening the door doesn't stop the magnetron, and start doesn't check it -
+    // the spaghetti microwave's first bug.
+    pub ignores_door: bool,
+    // Stop does nothing at all.
+    pub ignores_stop: bool,
+    // Running counts the time up instead of down, so it never finishes.
+    pub counts_up: bool,
+}
+
+// A microwave for tests. With no faults it follows the README table, same as the
+// model, so only the faults you switch on make it misbehave.
+#[derive(Clone, Debug)]
+pub struct Faulty {
+    faults: Faults,
+    door_open: bool,
+    running: bool,
+    // None is the NOTIME states. Some(0) is possible - setting a time of 0.
+    time: Option<usize>,
+}
+
+impl Faulty {
+    pub fn with(faults: Faults) -> Self {
+        Faulty {
+            faults,
+            door_open: false,
+            running: false,
+            time: None,
+        }
+    }
+
+    pub fn faults(&self) -> Faults {
+        self.faults
+    }
+}
+
+impl MicrowaveOps for Faulty {
+    fn new() -> Self {
+        Faulty::with(Faults::default())
+    }
+
+    // Faults are wiring, so they survive a reset.
+    fn reset(&mut self) {
+        *self = Faulty::with(self.faults);
+    }
+
+    fn tick(&mut self) {
+        if !self.running {
+            return;
+        }
+        let t = self.time.unwrap_or(0);
+        if self.faults.counts_up {
+            self.time = Some(t + 1);
+        } else if t <= 1 {
+            self.running = false;
+            self.time = None;
+        } else {
+            self.time = Some(t - 1);
+        }
+    }
+
+    fn magnetron_enabled(&self) -> bool {
+        self.running
+    }
+
+    fn door_open(&self) -> bool {
+        self.door_open
+    }
+
+    fn time_remain(&self) -> usize {
+        self.time.unwrap_or(0)
+    }
+
+    fn action_open_door(&mut self) {
+        self.door_open = true;
+        if !self.faults.ignores_door {
+            self.running = false;
+        }
+    }
+
+    fn action_close_door(&mut self) {
+        self.door_open = false;
+    }
+
+    fn action_set_time(&mut self, t: usize) {
+        if !self.running {
+            self.time = Some(t);
+        }
+    }
+
+    fn action_start(&mut self) {
+        if self.door_open && !self.faults.ignores_door {
+            return;
+        }
+        if self.running {
+            self.time = Some(self.time_remain() + 30);
+        } else {
+            self.time = Some(self.time.unwrap_or(30));
+            self.running = true;
+        }
+    }
+
+    fn action_stop(&mut self) {
+        if self.faults.ignores_stop {
+            return;
+        }
+        if self.running {
+            self.running = false;
+        } else {
+            self.time = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::faulty::{Faults, Faulty};
+    use crate::fuzzing::{check, decode};
+    use crate::{microwave_conformance_tests, MicrowaveOps};
+
+    microwave_conformance_tests!(Faulty);
+
+    #[test]
+    fn no_faults_is_the_model() {
+        let events = decode(&(0..=255).rev().collect::<Vec<u8>>());
+        assert!(check(&mut Faulty::new(), &events).is_ok());
+    }
+
+    #[test]
+    fn faults() {
+        let mut mw = Faulty::with(Faults {
+            ignores_door: true,
+            ..Faults::default()
+        });
+        mw.action_start();
+        mw.action_open_door();
+        assert!(mw.door_open() && mw.magnetron_enabled());
+        // And they're still there after a reset.
+        mw.reset();
+        mw.action_open_door();
+        mw.action_start();
+        assert!(mw.magnetron_enabled());
+
+        let mut mw = Faulty::with(Faults {
+            ignores_stop: true,
+            counts_up: true,
+            ..Faults::default()
+        });
+        mw.action_set_time(5);
+        mw.action_start();
+        mw.action_stop();
+        mw.tick();
+        assert!(mw.magnetron_enabled() && mw.time_remain() == 6);
+    }
+}
@@ -0,0 +1,216 @@
+use std::collections::VecDeque;
+
+use crate::{Event, MicrowaveOps};
+
+#[derive(Debug, PartialEq)]
+pub enum FleetError {
+    NoSuchMicrowave(usize),
+}
+
+// Several microwaves on one circuit, where only so many magnetrons can run at
+// once. Events are sent to a microwave by its id (its index), and all of them are
+// ticked together.
+//
+// Pressing start on a microwave that isn't running, when the budget is used up,
+// doesn't start it - it waits. Waiting microwaves start in the order start was
+// pressed, as soon as there's budget. A later start never jumps the queue, even if
+// budget happens to be free, so every waiting microwave gets its turn as long as
+// running ones finish.
+//
+// Pressing start with the door open doesn't queue, as it wouldn't have started
+// anyway. Opening the door, pressing stop, or a reset gives up our place in the
+// queue.
+pub struct Fleet<T: MicrowaveOps> {
+    microwaves: Vec<T>,
+    budget: usize,
+    queue: VecDeque<usize>,
+}
+
+impl<T: MicrowaveOps> Fleet<T> {
+    pub fn new(count: usize, budget: usize) -> Self {
+        Fleet::from_microwaves((0..count).map(|_| T::new()).collect(), budget)
+    }
+
+    pub fn from_microwaves(microwaves: Vec<T>, budget: usize) -> Self {
+        Fleet {
+            microwaves,
+            budget,
+            queue: VecDeque::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.microwaves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.microwaves.is_empty()
+    }
+
+    pub fn budget(&self) -> usize {
+        self.budget
+    }
+
+    pub fn microwave(&self, id: usize) -> Option<&T> {
+        self.microwaves.get(id)
+    }
+
+    // How many magnetrons are on right now.
+    pub fn running(&self) -> usize {
+        self.microwaves.iter().filter(|mw| mw.magnetron_enabled()).count()
+    }
+
+    pub fn is_waiting(&self, id: usize) -> bool {
+        self.queue.contains(&id)
+    }
+
+    // The waiting microwaves, next to start first.
+    pub fn waiting(&self) -> Vec<usize> {
+        self.queue.iter().copied().collect()
+    }
+
+    pub fn send(&mut self, id: usize, event: Event) -> Result<(), FleetError> {
+        let mw = self
+            .microwaves
+            .get_mut(id)
+            .ok_or(FleetError::NoSuchMicrowave(id))?;
+        match event {
+            Event::Tick => {
+                // A tick is for everyone, so the fleet has to be ticked as a whole.
+                self.tick();
+                return Ok(());
+            }
+            // Already running, so this doesn't use any more budget.
+            Event::Start if mw.magnetron_enabled() => mw.action_start(),
+            // Refused, so there's nothing to wait for. Queuing it would have it
+            // start by itself once the door was closed, with nobody pressing start.
+            Event::Start if mw.door_open() => {}
+            Event::Start => {
+                if !self.queue.contains(&id) {
+                    self.queue.push_back(id);
+                }
+            }
+            Event::OpenDoor | Event::Stop | Event::Reset => {
+                self.queue.retain(|w| *w != id);
+                event.apply(mw);
+            }
+            e => e.apply(mw),
+        }
+        self.admit();
+        Ok(())
+    }
+
+    pub fn tick(&mut self) {
+        for mw in self.microwaves.iter_mut() {
+            mw.tick();
+        }
+        self.admit();
+    }
+
+    // Start waiting microwaves, in order, while there's budget.
+    fn admit(&mut self) {
+        while self.running() < self.budget {
+            let id = match self.queue.pop_front() {
+                Some(id) => id,
+                None => break,
+            };
+            // Only a closed door gets into the queue, and opening it takes it back
+            // out, so this is a start it could have had when it was pressed.
+            self.microwaves[id].action_start();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fleet::{Fleet, FleetError};
+    use crate::fuzzing::decode;
+    use crate::model::Model;
+    use crate::{Event, MicrowaveOps};
+
+    #[test]
+    fn waits_in_turn() {
+        let mut f = Fleet::<Model>::new(3, 1);
+        f.send(0, Event::SetTime(2)).unwrap();
+        f.send(0, Event::Start).unwrap();
+        f.send(2, Event::Start).unwrap();
+        f.send(1, Event::Start).unwrap();
+        assert!(f.microwave(0).unwrap().magnetron_enabled());
+        assert!(f.waiting() == vec![2, 1]);
+        assert!(f.running() == 1);
+
+        // Adding time to a running microwave is fine.
+        f.send(0, Event::Start).unwrap();
+        assert!(f.microwave(0).unwrap().time_remain() == 32);
+        assert!(f.waiting() == vec![2, 1]);
+
+        f.send(0, Event::Stop).unwrap();
+        assert!(f.microwave(2).unwrap().magnetron_enabled());
+        assert!(f.waiting() == vec![1]);
+
+        // 0 starts again, but 1 was first.
+        f.send(0, Event::Start).unwrap();
+        assert!(f.waiting() == vec![1, 0]);
+        for _ in 0..30 {
+            f.tick();
+        }
+        assert!(f.microwave(1).unwrap().magnetron_enabled());
+        assert!(f.waiting() == vec![0]);
+
+        assert!(f.send(3, Event::Start) == Err(FleetError::NoSuchMicrowave(3)));
+    }
+
+    #[test]
+    fn give_up_place() {
+        let mut f = Fleet::<Model>::new(3, 1);
+        f.send(0, Event::Start).unwrap();
+        f.send(1, Event::Start).unwrap();
+        f.send(2, Event::Start).unwrap();
+        f.send(1, Event::OpenDoor).unwrap();
+        assert!(f.waiting() == vec![2]);
+
+        // Back in the queue, but at the end.
+        f.send(1, Event::CloseDoor).unwrap();
+        f.send(1, Event::Start).unwrap();
+        f.send(0, Event::Stop).unwrap();
+        assert!(f.microwave(2).unwrap().magnetron_enabled());
+        assert!(f.waiting() == vec![1]);
+    }
+
+    #[test]
+    fn door_open_never_queues() {
+        let mut f = Fleet::<Model>::new(2, 1);
+        f.send(0, Event::Start).unwrap();
+        f.send(1, Event::OpenDoor).unwrap();
+        f.send(1, Event::Start).unwrap();
+        assert!(f.waiting().is_empty());
+
+        // Nobody pressed start with the door closed, so it mustn't start.
+        f.send(1, Event::CloseDoor).unwrap();
+        f.send(0, Event::Stop).unwrap();
+        assert!(f.running() == 0);
+        assert!(!f.microwave(1).unwrap().magnetron_enabled());
+    }
+
+    #[test]
+    fn never_over_budget() {
+        // Lots of random events to random microwaves, checking after every one.
+        let mut x: u32 = 0x2545_f491;
+        let mut bytes = Vec::new();
+        for _ in 0..20_000 {
+            x ^= x << 13;
+            x ^= x >> 17;
+            x ^= x << 5;
+            bytes.push(x as u8);
+        }
+        for budget in 0..4 {
+            let mut f = Fleet::<Model>::new(5, budget);
+            for (i, e) in decode(&bytes).into_iter().enumerate() {
+                f.send(i % 5, e).unwrap();
+                assert!(f.running() <= budget);
+                // Nobody waits while there is budget to spare.
+                assert!(f.waiting().is_empty() || f.running() == budget);
+            }
+        }
+    }
+}
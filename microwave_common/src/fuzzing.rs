@@ -0,0 +1,155 @@
+use crate::model::Model;
+use crate::monitor::Monitor;
+use crate::{Event, MicrowaveOps, Outputs, ThermalOps, TraceRecord};
+
+// Turn arbitrary bytes into events, so a fuzzer can drive a microwave. Each byte
+// picks an event, and set_time takes the next byte as the time - kept small, as
+// large times only make the interesting part (running out) harder to reach.
+pub fn decode(data: &[u8]) -> Vec<Event> {
+    let mut events = Vec::new();
+    let mut bytes = data.iter();
+    while let Some(b) = bytes.next() {
+        events.push(match b % 7 {
+            0 => Event::Reset,
+            1 => Event::OpenDoor,
+            2 => Event::CloseDoor,
+            3 => Event::SetTime(bytes.next().map(|t| *t as usize % 64).unwrap_or(0)),
+            4 => Event::Start,
+            5 => Event::Stop,
+            _ => Event::Tick,
+        });
+    }
+    events
+}
+
+// What went wrong, and how we got there.
+#[derive(Debug, PartialEq)]
+pub struct Violation {
+    pub step: usize,
+    pub event: Event,
+    pub reason: String,
+}
+
+// Drive the microwave through the events alongside the reference model, checking
+// after every step that:
+//
+// * the properties of monitor::Monitor hold - never on with the door open, and
+//   never on for longer than the time it was started with
+// * while running, time never goes up, except when start adds more
+// * the outputs are the same as the model's
+pub fn check<T: MicrowaveOps>(mw: &mut T, events: &[Event]) -> Result<(), Violation> {
+    check_while(mw, events, |_| true)
+}
+
+// As check, but stop once modelled is false. For microwaves that do more than the
+// model knows about - we can only compare them up to the point they do it.
+pub fn check_while<T, F>(mw: &mut T, events: &[Event], modelled: F) -> Result<(), Violation>
+where
+    T: MicrowaveOps,
+    F: Fn(&T) -> bool,
+{
+    let mut model = Model::new();
+    let mut monitor = Monitor::new();
+    mw.reset();
+    for (step, event) in events.iter().enumerate() {
+        let before = Outputs::of(mw);
+        event.apply(mw);
+        event.apply(&mut model);
+        let after = Outputs::of(mw);
+
+        let fail = |reason: String| {
+            Err(Violation {
+                step,
+                event: *event,
+                reason,
+            })
+        };
+        if let Err(v) = monitor.observe(&TraceRecord { event: *event, outputs: after }) {
+            return fail(format!("{}: {}", v.property, v.reason));
+        }
+        if before.magnetron
+            && after.magnetron
+            && *event != Event::Start
+            && after.time_remain > before.time_remain
+        {
+            return fail(format!(
+                "time went up from {} to {} while running",
+                before.time_remain, after.time_remain
+            ));
+        }
+        if !modelled(mw) {
+            return Ok(());
+        }
+        let expected = Outputs::of(&model);
+        if after != expected {
+            return fail(format!("expected {}, found {}", expected, after));
+        }
+    }
+    Ok(())
+}
+
+fn report(r: Result<(), Violation>) {
+    if let Err(v) = r {
+        panic!("step {} ({}): {}", v.step, v.event, v.reason);
+    }
+}
+
+// For fuzz targets - panic, so the fuzzer saves the input.
+pub fn fuzz<T: MicrowaveOps>(data: &[u8]) {
+    report(check(&mut T::new(), &decode(data)));
+}
+
+// The model has no idea about temperature, so once the cut-out trips we can't say
+// what should happen next. Stop the run there.
+pub fn fuzz_thermal<T: ThermalOps>(data: &[u8]) {
+    report(check_while(&mut T::new(), &decode(data), |mw| !mw.overheated()));
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::faulty::{Faults, Faulty};
+    use crate::fuzzing::{check, check_while, decode};
+    use crate::model::Model;
+    use crate::{Event, MicrowaveOps};
+
+    #[test]
+    fn decoding() {
+        assert!(decode(&[]).is_empty());
+        assert!(
+            decode(&[2, 3, 90, 4, 6, 13, 7])
+                == vec![Event::CloseDoor, Event::SetTime(26), Event::Start, Event::Tick, Event::Tick, Event::Reset]
+        );
+        // Running out of bytes for the time.
+        assert!(decode(&[3]) == vec![Event::SetTime(0)]);
+    }
+
+    #[test]
+    fn checks() {
+        // The model agrees with itself, whatever happens.
+        let events = decode(&(0..=255).collect::<Vec<u8>>());
+        assert!(check(&mut Model::new(), &events).is_ok());
+        // Including a zero second cook, which the table allows.
+        let events = [Event::SetTime(0), Event::Start, Event::Tick, Event::Start];
+        assert!(check(&mut Model::new(), &events).is_ok());
+
+        // Never stops, and counts up.
+        let mut mw = Faulty::with(Faults {
+            ignores_stop: true,
+            counts_up: true,
+            ..Faults::default()
+        });
+        let v = check(&mut mw, &[Event::SetTime(5), Event::Start, Event::Tick]).unwrap_err();
+        assert!(v.step == 2);
+        assert!(v.reason == "time went up from 5 to 6 while running");
+
+        let v = check(&mut mw, &[Event::SetTime(5), Event::Stop]).unwrap_err();
+        assert!(v.step == 1);
+        assert!(v.reason == "expected closed off 0, found closed off 5");
+
+        // Once it's doing something the model doesn't know about, we stop comparing.
+        let events = [Event::Start, Event::SetTime(5), Event::Stop];
+        assert!(check(&mut mw, &events).is_err());
+        assert!(check_while(&mut mw, &events, |mw| !mw.magnetron_enabled()).is_ok());
+        assert!(check_while(&mut mw, &events[1..], |mw| !mw.magnetron_enabled()).is_err());
+    }
+}
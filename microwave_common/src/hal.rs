@@ -0,0 +1,212 @@
+use crate::{Event, MicrowaveOps};
+
+// What the board is wired to. One trait per job, so the lamp can't be plugged in
+// where the relay should be. Reads take &mut self as reading a real pin often
+// needs it.
+
+// High when the door is shut.
+pub trait DoorSwitch {
+    fn is_closed(&mut self) -> bool;
+}
+
+// High while held down.
+pub trait Button {
+    fn is_pressed(&mut self) -> bool;
+}
+
+// Switches the magnetron.
+pub trait Relay {
+    fn set_energised(&mut self, on: bool);
+}
+
+pub trait Lamp {
+    fn set_lit(&mut self, on: bool);
+}
+
+pub trait Buzzer {
+    fn set_sounding(&mut self, on: bool);
+}
+
+pub struct Pins<D, B, R, L, Z> {
+    pub door: D,
+    pub start: B,
+    pub stop: B,
+    pub relay: R,
+    pub lamp: L,
+    pub buzzer: Z,
+}
+
+// How many polls in a row an input has to read the same before we believe it.
+pub const DEBOUNCE_POLLS: u8 = 3;
+
+// How many ticks the buzzer sounds for when cooking is done.
+pub const BUZZER_TICKS: u8 = 2;
+
+// Switches bounce, and wires pick up noise, so a level only counts once it has
+// been seen DEBOUNCE_POLLS times in a row.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Debounce {
+    level: bool,
+    // How many polls in a row have disagreed with level.
+    count: u8,
+}
+
+impl Debounce {
+    const fn new(level: bool) -> Self {
+        Debounce { level, count: 0 }
+    }
+
+    fn update(&mut self, raw: bool) -> bool {
+        if raw == self.level {
+            self.count = 0;
+        } else {
+            self.count += 1;
+            if self.count >= DEBOUNCE_POLLS {
+                self.level = raw;
+                self.count = 0;
+            }
+        }
+        self.level
+    }
+
+    // Believe it straight away.
+    fn force(&mut self, level: bool) -> bool {
+        *self = Debounce::new(level);
+        level
+    }
+}
+
+// Sits between the state machine and the hardware. poll() should be called often
+// (every few milliseconds) to read the inputs, turn them into events, and write
+// the outputs. tick() is called once a second, as ever, and writes the outputs too
+// - the magnetron goes off the moment the time runs out, not a poll later.
+pub struct Board<T, D, B, R, L, Z> {
+    mw: T,
+    pins: Pins<D, B, R, L, Z>,
+    door_closed: Debounce,
+    // What the door switch said on the last poll, debounced or not.
+    raw_closed: bool,
+    start: Debounce,
+    stop: Debounce,
+    buzzing: u8,
+}
+
+impl<T, D, B, R, L, Z> Board<T, D, B, R, L, Z>
+where
+    T: MicrowaveOps,
+    D: DoorSwitch,
+    B: Button,
+    R: Relay,
+    L: Lamp,
+    Z: Buzzer,
+{
+    // A new microwave has its door closed, so that's what we start believing.
+    pub fn new(mw: T, pins: Pins<D, B, R, L, Z>) -> Self {
+        Board {
+            mw,
+            pins,
+            door_closed: Debounce::new(true),
+            raw_closed: true,
+            start: Debounce::new(false),
+            stop: Debounce::new(false),
+            buzzing: 0,
+        }
+    }
+
+    pub fn microwave(&self) -> &T {
+        &self.mw
+    }
+
+    // For the keypad, or anything else that isn't a pin.
+    pub fn microwave_mut(&mut self) -> &mut T {
+        &mut self.mw
+    }
+
+    pub fn pins(&self) -> &Pins<D, B, R, L, Z> {
+        &self.pins
+    }
+
+    // To rewire things while running, IE a new script for a mock pin.
+    pub fn pins_mut(&mut self) -> &mut Pins<D, B, R, L, Z> {
+        &mut self.pins
+    }
+
+    pub fn into_parts(self) -> (T, Pins<D, B, R, L, Z>) {
+        (self.mw, self.pins)
+    }
+
+    pub fn poll(&mut self) {
+        let was_closed = self.door_closed.level;
+        self.raw_closed = self.pins.door.is_closed();
+        // Opening is believed at once - if it was only a glitch, the worst that
+        // happens is the cooking pauses. Closing has to be steady.
+        let closed = if self.raw_closed {
+            self.door_closed.update(true)
+        } else {
+            self.door_closed.force(false)
+        };
+        if was_closed && !closed {
+            self.send(Event::OpenDoor);
+            self.buzzing = 0;
+        } else if !was_closed && closed {
+            self.send(Event::CloseDoor);
+        }
+
+        // Buttons act once per press, when it's first believed.
+        let was_pressed = self.start.level;
+        if self.start.update(self.pins.start.is_pressed()) && !was_pressed {
+            self.send(Event::Start);
+        }
+        let was_pressed = self.stop.level;
+        if self.stop.update(self.pins.stop.is_pressed()) && !was_pressed {
+            self.send(Event::Stop);
+        }
+
+        self.write();
+    }
+
+    pub fn tick(&mut self) {
+        let was_running = self.mw.magnetron_enabled();
+        self.send(Event::Tick);
+        self.buzzing = self.buzzing.saturating_sub(1);
+        if was_running && !self.mw.magnetron_enabled() && self.mw.time_remain() == 0 {
+            self.buzzing = BUZZER_TICKS;
+        }
+        self.write();
+    }
+
+    fn send(&mut self, event: Event) {
+        event.apply(&mut self.mw);
+    }
+
+    fn write(&mut self) {
+        let magnetron = self.mw.magnetron_enabled();
+        // Whatever the microwave thinks, never energise the relay while the switch
+        // says the door is open.
+        self.pins.relay.set_energised(magnetron && self.raw_closed);
+        self.pins.lamp.set_lit(magnetron || !self.door_closed.level);
+        self.pins.buzzer.set_sounding(self.buzzing > 0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hal::{Debounce, DEBOUNCE_POLLS};
+
+    #[test]
+    fn debounce() {
+        let mut d = Debounce::new(false);
+        // A blip is ignored, and resets the count.
+        for _ in 0..DEBOUNCE_POLLS - 1 {
+            assert!(!d.update(true));
+        }
+        assert!(!d.update(false));
+        for _ in 0..DEBOUNCE_POLLS - 1 {
+            assert!(!d.update(true));
+        }
+        assert!(d.update(true));
+        assert!(d.update(false));
+        assert!(!d.force(false));
+        assert!(!d.update(true));
+    }
+}
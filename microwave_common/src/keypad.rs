@@ -0,0 +1,168 @@
+use crate::MicrowaveOps;
+
+// The buttons on the front panel that enter a time.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Key {
+    Digit(u8),
+    Clear,
+    TimeCook,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum KeypadError {
+    // Digits only go to 9.
+    InvalidDigit(u8),
+    // The seconds entered were more than 59, IE 1:75.
+    SecondsOverflow,
+}
+
+// We only have four digits on the display, MM:SS.
+const MAX_DIGITS: usize = 4;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum KeypadState {
+    Idle,
+    // Digits shift in from the right, just like a real microwave. So typing 1, 3, 0
+    // gives 130, which is 1:30.
+    Entry { value: usize, digits: usize },
+}
+
+// A small state machine that sits in front of the cooking state machine. It turns
+// button presses into a time, and only once the time is valid does it commit it
+// with action_set_time.
+#[derive(Debug)]
+pub struct Keypad {
+    state: KeypadState,
+}
+
+impl Default for Keypad {
+    fn default() -> Self {
+        Keypad::new()
+    }
+}
+
+impl Keypad {
+    pub fn new() -> Self {
+        Keypad {
+            state: KeypadState::Idle,
+        }
+    }
+
+    pub fn press<T: MicrowaveOps>(&mut self, key: Key, mw: &mut T) -> Result<(), KeypadError> {
+        self.state = match (self.state, key) {
+            (_, Key::Digit(d)) if d > 9 => return Err(KeypadError::InvalidDigit(d)),
+            (KeypadState::Idle, Key::Digit(d)) => KeypadState::Entry {
+                value: d as usize,
+                digits: 1,
+            },
+            // The display is full, so extra digits are ignored.
+            (KeypadState::Entry { value, digits }, Key::Digit(_)) if digits == MAX_DIGITS => {
+                KeypadState::Entry { value, digits }
+            }
+            (KeypadState::Entry { value, digits }, Key::Digit(d)) => KeypadState::Entry {
+                value: value * 10 + d as usize,
+                digits: digits + 1,
+            },
+            (_, Key::Clear) => KeypadState::Idle,
+            (KeypadState::Idle, Key::TimeCook) => KeypadState::Idle,
+            (KeypadState::Entry { value, .. }, Key::TimeCook) => {
+                // Like a real microwave, an invalid entry is thrown away.
+                self.state = KeypadState::Idle;
+                if value % 100 > 59 {
+                    return Err(KeypadError::SecondsOverflow);
+                }
+                mw.action_set_time((value / 100) * 60 + value % 100);
+                KeypadState::Idle
+            }
+        };
+        Ok(())
+    }
+
+    // What has been typed so far, as (minutes, seconds), so it can be displayed.
+    pub fn entry(&self) -> Option<(usize, usize)> {
+        match self.state {
+            KeypadState::Idle => None,
+            KeypadState::Entry { value, .. } => Some((value / 100, value % 100)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::keypad::{Key, Keypad, KeypadError};
+    use crate::model::{Model, State};
+    use crate::MicrowaveOps;
+
+    // Nothing committed leaves the microwave with no time at all.
+    fn no_time(mw: &Model) -> bool {
+        mw.state() == State::ClosedNoTimeNoMtron
+    }
+
+    fn type_keys(keys: &[Key]) -> (Keypad, Model, Result<(), KeypadError>) {
+        let mut kp = Keypad::new();
+        let mut mw = Model::new();
+        let mut r = Ok(());
+        for k in keys {
+            r = kp.press(*k, &mut mw);
+        }
+        (kp, mw, r)
+    }
+
+    #[test]
+    fn shift_in() {
+        let (kp, mw, _) = type_keys(&[Key::Digit(1), Key::Digit(3)]);
+        assert!(kp.entry() == Some((0, 13)));
+        assert!(no_time(&mw));
+
+        let (kp, mw, r) = type_keys(&[Key::Digit(1), Key::Digit(3), Key::Digit(0), Key::TimeCook]);
+        assert!(r.is_ok());
+        assert!(kp.entry().is_none());
+        assert!(mw.time_remain() == 90);
+
+        let (_, mw, _) = type_keys(&[Key::Digit(4), Key::Digit(5), Key::TimeCook]);
+        assert!(mw.time_remain() == 45);
+
+        let (_, mw, _) = type_keys(&[Key::Digit(9), Key::Digit(9), Key::Digit(5), Key::Digit(9), Key::TimeCook]);
+        assert!(mw.time_remain() == 99 * 60 + 59);
+    }
+
+    #[test]
+    fn too_many_digits() {
+        let (kp, _, _) = type_keys(&[
+            Key::Digit(1),
+            Key::Digit(2),
+            Key::Digit(3),
+            Key::Digit(4),
+            Key::Digit(5),
+        ]);
+        assert!(kp.entry() == Some((12, 34)));
+    }
+
+    #[test]
+    fn seconds_overflow() {
+        let (kp, mw, r) = type_keys(&[Key::Digit(1), Key::Digit(7), Key::Digit(5), Key::TimeCook]);
+        assert!(r == Err(KeypadError::SecondsOverflow));
+        assert!(kp.entry().is_none());
+        assert!(no_time(&mw));
+
+        let (_, mw, r) = type_keys(&[Key::Digit(6), Key::Digit(0), Key::TimeCook]);
+        assert!(r == Err(KeypadError::SecondsOverflow));
+        assert!(no_time(&mw));
+    }
+
+    #[test]
+    fn clear_and_idle() {
+        let (kp, mw, _) = type_keys(&[Key::Digit(1), Key::Clear]);
+        assert!(kp.entry().is_none());
+        assert!(no_time(&mw));
+
+        // Time cook with nothing typed does nothing.
+        let (_, mw, r) = type_keys(&[Key::TimeCook]);
+        assert!(r.is_ok());
+        assert!(no_time(&mw));
+
+        let (kp, _, r) = type_keys(&[Key::Digit(1), Key::Digit(10)]);
+        assert!(r == Err(KeypadError::InvalidDigit(10)));
+        assert!(kp.entry() == Some((0, 1)));
+    }
+}
@@ -1,6 +1,55 @@
+// The trait, the model and anything else that only needs core work without std, so
+// a state machine can go on a microcontroller. The test harness and the rest need
+// the std feature, which is on by default.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "std")]
+pub mod audit;
+#[cfg(feature = "std")]
+pub mod clock;
+#[cfg(feature = "std")]
+pub mod coverage;
+pub mod display;
+#[cfg(feature = "std")]
+pub mod driver;
+pub mod event;
+// Only for tests, here or in crates that turn on the testing feature.
+#[cfg(any(test, feature = "testing"))]
+pub mod faulty;
+#[cfg(feature = "std")]
+pub mod fleet;
+#[cfg(feature = "std")]
+pub mod fuzzing;
+pub mod hal;
+pub mod keypad;
+#[cfg(feature = "std")]
+pub mod mock_board;
+pub mod model;
+#[cfg(feature = "std")]
+pub mod monitor;
+#[cfg(feature = "std")]
+pub mod mutation;
+#[cfg(feature = "std")]
+pub mod report;
+pub mod safe;
+#[cfg(feature = "std")]
+pub mod scenario;
+pub mod snapshot;
+pub mod stats;
+
+pub use crate::event::{Event, Outputs, TraceRecord};
+
+#[cfg(feature = "std")]
+use crate::report::{ConformanceReport, GroupReport};
+#[cfg(feature = "std")]
+use crate::scenario::{run_scenario, Scenario};
 
 pub trait MicrowaveOps {
-    fn new() -> Self;
+    // Sized, so the rest can be used through &mut dyn MicrowaveOps - on a
+    // microcontroller the microwave is usually made once, at startup.
+    fn new() -> Self
+    where
+        Self: Sized;
     fn reset(&mut self);
 
     fn tick(&mut self);
@@ -16,251 +65,158 @@ pub trait MicrowaveOps {
     fn action_stop(&mut self);
 }
 
-macro_rules! assert_mw {
-    (
-        $mw:expr,
-        $door:expr,
-        $mtron:expr,
-        $time:expr
-    ) => {{
-        // Assert we never have an unsafe combo:
-        if $mw.magnetron_enabled() {
-            assert!($mw.door_open() == false)
-        }
-        let d = $mw.door_open();
-        let m = $mw.magnetron_enabled();
-        let t = $mw.time_remain();
-        println!("state: d {} m {} t {}", d, m, t);
-        assert!(d == $door);
-        assert!(m == $mtron);
-        assert!(t == $time);
-    }};
-}
-
-pub fn test_microwave<T: MicrowaveOps>(mw: &mut T) -> bool {
-    // Test 1 - check that from both door-open states, pressing start will never activate the
-    // magnetron.
-    println!("Test 1");
-    mw.reset();
-    // CLOSED_NOTIME_NOMTRON -> OPEN_NOTIME
-    mw.action_open_door();
-    assert_mw!(mw, true, false, 0);
-    // OPEN_NOTIME -> CLOSED_NOTIME_NOMTRON
-    mw.action_close_door();
-    assert_mw!(mw, false, false, 0);
-    // CLOSED_NOTIME_NOMTRON -> OPEN_NOTIME
-    mw.action_open_door();
-    assert_mw!(mw, true, false, 0);
-
-    // OPEN_NOTIME -> X
-    mw.action_start();
-    assert_mw!(mw, true, false, 0);
-    // OPEN_NOTIME -> X
-    mw.action_stop();
-    assert_mw!(mw, true, false, 0);
-
-    // OPEN_NOTIME -> OPEN_TIME
-    mw.action_set_time(20);
-    assert_mw!(mw, true, false, 20);
-    // OPEN_TIME -> OPEN_TIME
-    mw.action_set_time(30);
-    assert_mw!(mw, true, false, 30);
-    // OPEN_TIME -> X
-    mw.action_open_door();
-    assert_mw!(mw, true, false, 30);
-    // OPEN_TIME -> X
-    mw.action_start();
-    assert_mw!(mw, true, false, 30);
-
-    // OPEN_TIME -> CLOSED_NOTIME_NOMTRON
-    mw.action_close_door();
-    assert_mw!(mw, false, false, 30);
-    // CLOSED_NOTIME_NOMTRON -> OPEN_TIME
-    mw.action_open_door();
-    assert_mw!(mw, true, false, 30);
-
-    // OPEN_TIME -> OPEN_NOTIME
-    mw.action_stop();
-    assert_mw!(mw, true, false, 0);
-
+// Real microwaves don't have one door switch, they have two (or three!) and refuse to run
+// if they disagree. This replaces the single open/close door input with independent
+// switch inputs. The door is only closed when *both* switches say it is.
+pub trait InterlockOps: MicrowaveOps {
+    fn action_primary_switch(&mut self, closed: bool);
+    fn action_secondary_switch(&mut self, closed: bool);
 
-    // Test 2 - check that when the magnetron is disabled, time ticks have no effect.
-    println!("Test 2");
-    mw.reset();
-    mw.action_open_door();
-    assert_mw!(mw, true, false, 0);
-    // OPEN_NOTIME -> X
-    mw.tick();
-    assert_mw!(mw, true, false, 0);
-
-    mw.reset();
-    mw.action_open_door();
-    assert_mw!(mw, true, false, 0);
-    mw.action_set_time(30);
-    assert_mw!(mw, true, false, 30);
-    // OPEN_TIME -> X
-    mw.tick();
-    assert_mw!(mw, true, false, 30);
-
-    mw.reset();
-    mw.action_close_door();
-    // CLOSED_NOTIME_NOMTRON -> X
-    mw.tick();
-    assert_mw!(mw, false, false, 0);
-
-    mw.reset();
-    mw.action_close_door();
-    assert_mw!(mw, false, false, 0);
-    mw.action_set_time(30);
-    assert_mw!(mw, false, false, 30);
-    // CLOSED_TIME_NOMTRON -> X
-    mw.tick();
-    assert_mw!(mw, false, false, 30);
-
-    // This set's the time *before* we close the door to exercise open_time
-    // to close_time_nomtron
-    mw.reset();
-    mw.action_open_door();
-    mw.action_set_time(30);
-    assert_mw!(mw, true, false, 30);
-    mw.action_close_door();
-    assert_mw!(mw, false, false, 30);
-    // CLOSED_TIME_NOMTRON -> X
-    mw.tick();
-    assert_mw!(mw, false, false, 30);
-
-    // Test 3 - time to heat some food my friens.
-    // Check the timer decrements and ends as expected.
-    println!("Test 3");
-    mw.reset();
-    // CLOSED_NOTIME_NOMTRON -> X
-    mw.action_close_door();
-    // CLOSED_NOTIME_NOMTRON -> CLOSED_TIME_NOMTRON
-    mw.action_set_time(2);
-    assert_mw!(mw, false, false, 2);
-    // CLOSED_TIME_NOMTRON -> CLOSED_TIME_MTRON
-    mw.action_start();
-    assert_mw!(mw, false, true, 2);
-    // CLOSED_TIME_MTRON -> X
-    mw.tick();
-    assert_mw!(mw, false, true, 1);
-    // CLOSED_TIME_MTRON -> CLOSED_NOTIME_NOMTRON
-    mw.tick();
-    assert_mw!(mw, false, false, 0);
-
-    // Test 4 - Check that pressing start with no time advances to 30. We check some other interactions
-    // of pressing the start with time also.
-    println!("Well done to make it to this point! 🎉");
-    println!("The tests get harder now ...");
-    println!("Test 4");
-    mw.reset();
-    // CLOSED_NOTIME_NOMTRON
-    mw.action_close_door();
-    assert_mw!(mw, false, false, 0);
-    // CLOSED_NOTIME_NOMTRON -> CLOSED_TIME_MTRON
-    mw.action_start();
-    assert_mw!(mw, false, true, 30);
-    // CLOSED_TIME_MTRON -> X
-    mw.action_start();
-    assert_mw!(mw, false, true, 60);
-    mw.tick();
-    assert_mw!(mw, false, true, 59);
-    // CLOSED_TIME_MTRON -> OPEN_TIME
-    mw.action_open_door();
-    assert_mw!(mw, true, false, 59);
-    // OPEN_TIME -> CLOSED_TIME_NOMTRON
-    mw.action_close_door();
-    mw.action_start();
-    assert_mw!(mw, false, true, 59);
-    mw.tick();
-    assert_mw!(mw, false, true, 58);
-    // CLOSED_TIME_MTRON -> CLOSED_TIME_NOMTRON
-    mw.action_stop();
-    assert_mw!(mw, false, false, 58);
-    // CLOSED_TIME_NOMTRON -> X
-    mw.tick();
-    assert_mw!(mw, false, false, 58);
-    // CLOSED_TIME_NOMTRON -> CLOSED_NOTIME_NOMTRON
-    mw.action_stop();
-    assert_mw!(mw, false, false, 0);
-
-    // Test 5 - check that changes to time while running have no effect.
-    println!("Test 5");
-    mw.reset();
-    mw.action_close_door();
-    // CLOSED_TIME_MTRON
-    mw.action_start();
-    assert_mw!(mw, false, true, 30);
-    // CLOSED_TIME_MTRON -> X
-    mw.action_set_time(25);
-    assert_mw!(mw, false, true, 30);
-    // CLOSED_TIME_MTRON -> X
-    mw.action_set_time(45);
-    assert_mw!(mw, false, true, 30);
-    // CLOSED_TIME_MTRON -> OPEN_TIME
-    mw.action_open_door();
-    assert_mw!(mw, true, false, 30);
-    // OPEN_TIME -> OPEN_TIME
-    mw.action_set_time(45);
-    assert_mw!(mw, true, false, 45);
+    // Switches that disagree for too long latch a fault - only a reset clears it.
+    fn interlock_fault(&self) -> bool;
+}
 
-    // Test 6 - Time to test weird stuff.
-    // This tests all the edge cases that we normally don't think of like double closes
-    // or time changes in certain states.
-    println!("Test 6");
-    mw.reset();
+// magnetron_enabled() is what we *asked* for. The relay that switches the magnetron can
+// weld shut, so a current sensor tells us what actually happened during each second.
+pub trait RelayFeedbackOps: MicrowaveOps {
+    // One second elapses, during which the magnetron was (or was not) sensed drawing current.
+    fn tick_sensed(&mut self, magnetron_sensed: bool);
 
-    // CLOSED_NOTIME_NOMTRON -> OPEN_NOTIME
-    mw.action_open_door();
-    assert_mw!(mw, true, false, 0);
-    // OPEN_NOTIME -> X
-    mw.action_open_door();
-    assert_mw!(mw, true, false, 0);
+    // The magnetron was sensed on when it must have been off. Only a reset clears it.
+    fn relay_fault(&self) -> bool;
+}
 
-    // OPEN_NOTIME -> OPEN_TIME
-    mw.action_set_time(24);
-    assert_mw!(mw, true, false, 24);
-    // OPEN_TIME -> X
-    mw.action_open_door();
-    assert_mw!(mw, true, false, 24);
+// Running the magnetron heats the cavity. Too hot and the microwave refuses to start until
+// it has cooled down again.
+pub trait ThermalOps: MicrowaveOps {
+    fn cavity_temp(&self) -> usize;
+    fn overheated(&self) -> bool;
+}
 
-    // OPEN_TIME -> CLOSED_TIME_NOMTRON
-    mw.action_close_door();
-    assert_mw!(mw, false, false, 24);
-    // CLOSED_TIME_NOMTRON -> X
-    mw.action_set_time(389);
-    assert_mw!(mw, false, false, 389);
-    // CLOSED_TIME_NOMTRON -> X
-    mw.action_close_door();
-    assert_mw!(mw, false, false, 389);
-    // CLOSED_TIME_NOMTRON -> OPEN_TIME
-    mw.action_open_door();
-    assert_mw!(mw, true, false, 389);
+// Run every scenario against the microwave. Each group starts from a reset, so one
+// failure doesn't hide the rest - check the report to see everything that is wrong.
+#[cfg(feature = "std")]
+pub fn test_microwave<T: MicrowaveOps>(mw: &mut T) -> ConformanceReport {
+    let groups = Scenario::builtin()
+        .iter()
+        .map(|scenario| {
+            mw.reset();
+            run_scenario(mw, scenario)
+        })
+        .collect();
+    ConformanceReport { groups }
+}
 
+// Run just one scenario, by name, from a reset.
+#[cfg(feature = "std")]
+pub fn test_group<T: MicrowaveOps>(mw: &mut T, name: &str) -> GroupReport {
+    let scenario = Scenario::builtin()
+        .into_iter()
+        .find(|s| s.name == name)
+        .unwrap_or_else(|| panic!("no scenario named {}", name));
     mw.reset();
-    // CLOSED_NOTIME_NOMTRON -> X
-    mw.action_close_door();
-    assert_mw!(mw, false, false, 0);
-    // CLOSED_NOTIME_NOMTRON -> X
-    mw.action_close_door();
-    assert_mw!(mw, false, false, 0);
-    // CLOSED_NOTIME_NOMTRON -> X
-    mw.action_stop();
-    assert_mw!(mw, false, false, 0);
-    // CLOSED_NOTIME_NOMTRON -> OPEN_NOTIME
-    mw.action_open_door();
-    assert_mw!(mw, true, false, 0);
-
-    // OPEN_NOTIME -> CLOSED_NOTIME_NOMTRON -> CLOSED_TIME_MTRON
-    mw.action_close_door();
-    assert_mw!(mw, false, false, 0);
-    mw.action_start();
-    assert_mw!(mw, false, true, 30);
-    // CLOSED_TIME_MTRON -> X
-    mw.action_close_door();
-    assert_mw!(mw, false, true, 30);
+    run_scenario(mw, &scenario)
+}
 
-    println!("✨ Your implementation passes! ✨");
-    true
+// Expands to one #[test] per scenario, so cargo test can tell you which one failed.
+// Use it in your tests module, IE:
+//
+//     microwave_conformance_tests!(Microwave);
+//
+// The named tests are only the scenarios we had when this was written. A new file
+// in scenarios/ doesn't need adding here, as all_scenarios runs every one of them.
+#[cfg(feature = "std")]
+#[macro_export]
+macro_rules! microwave_conformance_tests {
+    ($mw:ty) => {
+        $crate::microwave_conformance_tests!(
+            $mw,
+            door_open_start_refusal,
+            tick_while_stopped,
+            countdown,
+            quick_start,
+            running_time_changes,
+            edge_cases
+        );
+
+        #[test]
+        fn all_scenarios() {
+            let mut mw = <$mw as $crate::MicrowaveOps>::new();
+            let report = $crate::test_microwave(&mut mw);
+            println!("{}", report);
+            assert!(report.passed());
+        }
+    };
+    ($mw:ty, $($group:ident),+) => {
+        $(
+            #[test]
+            fn $group() {
+                let mut mw = <$mw as $crate::MicrowaveOps>::new();
+                let report = $crate::test_group(&mut mw, stringify!($group));
+                println!("{}", report);
+                assert!(report.passed());
+            }
+        )+
+    };
 }
 
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use std::process::Command;
+
+    use crate::model::Model;
+    use crate::MicrowaveOps;
+
+    // Something without std, like a Cortex-M4.
+    const NO_STD_TARGET: &str = "thumbv7em-none-eabi";
+
+    #[test]
+    fn dyn_microwave() {
+        let mut model = Model::new();
+        let mw: &mut dyn MicrowaveOps = &mut model;
+        mw.action_start();
+        mw.tick();
+        assert!(mw.magnetron_enabled() && mw.time_remain() == 29);
+    }
+
+    // Whether the target's core library is installed, IE with:
+    //
+    //     rustup target add thumbv7em-none-eabi
+    fn no_std_installed() -> bool {
+        Command::new("rustc")
+            .args(["--print", "sysroot"])
+            .output()
+            .map(|o| {
+                let sysroot = String::from_utf8_lossy(&o.stdout).trim().to_string();
+                Path::new(&sysroot).join("lib").join("rustlib").join(NO_STD_TARGET).exists()
+            })
+            .unwrap_or(false)
+    }
+
+    // Keeps the microwave buildable for a microcontroller, with and without serde.
+    // Skipped if the target isn't installed.
+    #[test]
+    fn no_std() {
+        if !no_std_installed() {
+            println!("skipped, {} isn't installed", NO_STD_TARGET);
+            return;
+        }
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("..");
+        for krate in &["microwave_common", "rust_microwave_simple"] {
+            for features in &[&[][..], &["--features", "serde"][..]] {
+                // From inside the crate, so --no-default-features only applies to it, and
+                // with its own target dir, so we don't wait on the lock cargo test holds.
+                let status = Command::new(env!("CARGO"))
+                    .args(["check", "--no-default-features", "--target", NO_STD_TARGET])
+                    .args(*features)
+                    .arg("--target-dir")
+                    .arg(root.join("target").join("no_std"))
+                    .current_dir(root.join(krate))
+                    .status()
+                    .unwrap();
+                assert!(status.success(), "{} {:?} doesn't build without std", krate, features);
+            }
+        }
+    }
+}
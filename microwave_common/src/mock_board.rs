@@ -0,0 +1,233 @@
+use crate::hal::{Board, Button, Buzzer, DoorSwitch, Lamp, Pins, Relay};
+
+// A board for testing on a machine without any pins. Inputs are scripted, one
+// level per poll, and outputs remember every level they were set to. Levels are
+// written like a logic analyser would show them, '-' high and '_' low, IE:
+//
+//     door:  "------_-----"
+//     relay: "___---_____"
+
+fn level(c: char) -> bool {
+    match c {
+        '-' => true,
+        '_' => false,
+        _ => panic!("'{}' isn't a level, use '-' or '_'", c),
+    }
+}
+
+pub struct ScriptedInput {
+    levels: Vec<bool>,
+    read: usize,
+}
+
+impl ScriptedInput {
+    // Once the script runs out, the last level holds.
+    pub fn new(script: &str) -> Self {
+        ScriptedInput {
+            levels: script.chars().filter(|c| !c.is_whitespace()).map(level).collect(),
+            read: 0,
+        }
+    }
+
+    fn read(&mut self) -> bool {
+        let l = self
+            .levels
+            .get(self.read)
+            .or_else(|| self.levels.last())
+            .copied()
+            .unwrap_or(false);
+        self.read += 1;
+        l
+    }
+
+    // Swap in a new script, from the next read.
+    pub fn script(&mut self, script: &str) {
+        *self = ScriptedInput::new(script);
+    }
+}
+
+impl DoorSwitch for ScriptedInput {
+    fn is_closed(&mut self) -> bool {
+        self.read()
+    }
+}
+
+impl Button for ScriptedInput {
+    fn is_pressed(&mut self) -> bool {
+        self.read()
+    }
+}
+
+#[derive(Default)]
+pub struct RecordedOutput {
+    pub levels: Vec<bool>,
+}
+
+impl RecordedOutput {
+    pub fn level(&self) -> bool {
+        self.levels.last().copied().unwrap_or(false)
+    }
+
+    pub fn trace(&self) -> String {
+        self.levels.iter().map(|l| if *l { '-' } else { '_' }).collect()
+    }
+
+    fn set(&mut self, on: bool) {
+        self.levels.push(on);
+    }
+}
+
+impl Relay for RecordedOutput {
+    fn set_energised(&mut self, on: bool) {
+        self.set(on)
+    }
+}
+
+impl Lamp for RecordedOutput {
+    fn set_lit(&mut self, on: bool) {
+        self.set(on)
+    }
+}
+
+impl Buzzer for RecordedOutput {
+    fn set_sounding(&mut self, on: bool) {
+        self.set(on)
+    }
+}
+
+pub type MockPins = Pins<ScriptedInput, ScriptedInput, RecordedOutput, RecordedOutput, RecordedOutput>;
+
+pub type MockBoard<T> = Board<T, ScriptedInput, ScriptedInput, RecordedOutput, RecordedOutput, RecordedOutput>;
+
+pub fn mock_pins(door: &str, start: &str, stop: &str) -> MockPins {
+    Pins {
+        door: ScriptedInput::new(door),
+        start: ScriptedInput::new(start),
+        stop: ScriptedInput::new(stop),
+        relay: RecordedOutput::default(),
+        lamp: RecordedOutput::default(),
+        buzzer: RecordedOutput::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hal::Board;
+    use crate::mock_board::{mock_pins, MockBoard};
+    use crate::model::{Model, State};
+    use crate::MicrowaveOps;
+
+    fn board(door: &str, start: &str, stop: &str) -> MockBoard<Model> {
+        Board::new(Model::new(), mock_pins(door, start, stop))
+    }
+
+    fn polls(board: &mut MockBoard<Model>, n: usize) {
+        for _ in 0..n {
+            board.poll();
+        }
+    }
+
+    #[test]
+    fn cook() {
+        let mut b = board("-", "__---_", "_");
+        polls(&mut b, 6);
+        // Start is believed on its third poll, and the outputs follow at once.
+        assert!(b.pins().relay.trace() == "____--");
+        assert!(b.pins().lamp.trace() == "____--");
+        assert!(b.microwave().time_remain() == 30);
+
+        // Cook it all.
+        for _ in 0..30 {
+            b.tick();
+            b.poll();
+        }
+        // Ticks write the outputs too, so each second is two levels.
+        assert!(b.pins().relay.trace() == format!("____{}__", "-".repeat(60)));
+        assert!(!b.pins().lamp.level());
+        assert!(b.pins().buzzer.trace().ends_with("_--"));
+        b.tick();
+        b.poll();
+        b.tick();
+        b.poll();
+        assert!(b.pins().buzzer.trace().ends_with("_----__"));
+    }
+
+    #[test]
+    fn relay_drops_on_tick() {
+        let mut b = board("-", "---_", "_");
+        polls(&mut b, 4);
+        for _ in 0..29 {
+            b.tick();
+        }
+        assert!(b.pins().relay.level());
+        // The time runs out, and the relay drops there and then - not on the next
+        // poll, which could be a while.
+        b.tick();
+        assert!(!b.pins().relay.level());
+        assert!(b.pins().buzzer.level());
+    }
+
+    #[test]
+    fn button_glitches() {
+        // Too short to be a press.
+        let mut b = board("-", "_--_-_--_", "_");
+        polls(&mut b, 12);
+        assert!(!b.pins().relay.trace().contains('-'));
+
+        // Held down is only one press - so only 30 seconds, not 60.
+        let mut b = board("-", "_-----------_", "_");
+        polls(&mut b, 13);
+        assert!(b.microwave().time_remain() == 30);
+
+        // And stop works the same way.
+        b.pins_mut().stop.script("-_---");
+        polls(&mut b, 5);
+        assert!(b.pins().relay.trace() == format!("___{}_", "-".repeat(14)));
+        assert!(b.microwave().state() == State::ClosedTimeNoMtron);
+    }
+
+    #[test]
+    fn door_glitches() {
+        // Start cooking, then the door switch blips open for one poll.
+        let mut b = board("-------_------", "---_", "_");
+        polls(&mut b, 14);
+        // The relay drops straight away, and cooking pauses until start is pressed
+        // again - even though the door was never really opened.
+        assert!(b.pins().relay.trace() == "__-----_______");
+        assert!(b.pins().lamp.trace() == "__--------____");
+        assert!(b.microwave().state() == State::ClosedTimeNoMtron);
+
+        // A door that blips closed while open stays open.
+        let mut b = board("__-__--_____", "_", "_");
+        polls(&mut b, 12);
+        assert!(b.microwave().door_open());
+        assert!(b.pins().lamp.trace() == "------------");
+    }
+
+    // Whatever the microwave does, the relay is never on while the switch reads
+    // open - even on the poll where it first does.
+    #[test]
+    fn relay_follows_switch() {
+        // A tiny LCG, so this doesn't need a dependency.
+        let mut seed = 0x2545_f491u32;
+        let mut next = move || {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            !(seed >> 16).is_multiple_of(4)
+        };
+        let door: String = (0..2000).map(|_| if next() { '-' } else { '_' }).collect();
+        let start: String = (0..2000).map(|_| if next() { '-' } else { '_' }).collect();
+        let mut b = board(&door, &start, "_");
+        let mut energised = false;
+        for (i, d) in door.chars().enumerate() {
+            b.poll();
+            assert!(!(d == '_' && b.pins().relay.level()));
+            // A tick knows no better than the poll before it.
+            if i.is_multiple_of(50) {
+                b.tick();
+                assert!(!(d == '_' && b.pins().relay.level()));
+            }
+            energised |= b.pins().relay.level();
+        }
+        assert!(energised);
+    }
+}
@@ -0,0 +1,270 @@
+use crate::{Event, MicrowaveOps};
+
+// The five states from the README.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum State {
+    OpenNoTime,
+    OpenTime,
+    ClosedNoTimeNoMtron,
+    ClosedTimeNoMtron,
+    ClosedTimeMtron,
+}
+
+impl State {
+    pub const ALL: [State; 5] = [
+        State::OpenNoTime,
+        State::OpenTime,
+        State::ClosedNoTimeNoMtron,
+        State::ClosedTimeNoMtron,
+        State::ClosedTimeMtron,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            State::OpenNoTime => "OPEN_NOTIME",
+            State::OpenTime => "OPEN_TIME",
+            State::ClosedNoTimeNoMtron => "CLOSED_NOTIME_NOMTRON",
+            State::ClosedTimeNoMtron => "CLOSED_TIME_NOMTRON",
+            State::ClosedTimeMtron => "CLOSED_TIME_MTRON",
+        }
+    }
+
+    pub(crate) fn index(self) -> usize {
+        State::ALL.iter().position(|s| *s == self).unwrap()
+    }
+}
+
+// The six inputs from the README. Reset isn't one - it's pulling the plug out.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Input {
+    OpenDoor,
+    CloseDoor,
+    SetTime,
+    Stop,
+    Start,
+    OneSecond,
+}
+
+impl Input {
+    pub const ALL: [Input; 6] = [
+        Input::OpenDoor,
+        Input::CloseDoor,
+        Input::SetTime,
+        Input::Stop,
+        Input::Start,
+        Input::OneSecond,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Input::OpenDoor => "open door",
+            Input::CloseDoor => "close door",
+            Input::SetTime => "set time",
+            Input::Stop => "stop",
+            Input::Start => "start",
+            Input::OneSecond => "one second",
+        }
+    }
+
+    pub fn of(event: Event) -> Option<Input> {
+        match event {
+            Event::Reset => None,
+            Event::OpenDoor => Some(Input::OpenDoor),
+            Event::CloseDoor => Some(Input::CloseDoor),
+            Event::SetTime(_) => Some(Input::SetTime),
+            Event::Stop => Some(Input::Stop),
+            Event::Start => Some(Input::Start),
+            Event::Tick => Some(Input::OneSecond),
+        }
+    }
+
+    pub(crate) fn index(self) -> usize {
+        Input::ALL.iter().position(|i| *i == self).unwrap()
+    }
+}
+
+use self::State::*;
+
+// The answer table from the README, row by row. The one second row for
+// CLOSED_TIME_MTRON has two answers - the time running out is handled in tick().
+pub type Table = [[State; 5]; 6];
+
+pub const TABLE: Table = [
+    // open door
+    [OpenNoTime, OpenTime, OpenNoTime, OpenTime, OpenTime],
+    // close door
+    [ClosedNoTimeNoMtron, ClosedTimeNoMtron, ClosedNoTimeNoMtron, ClosedTimeNoMtron, ClosedTimeMtron],
+    // set time
+    [OpenTime, OpenTime, ClosedTimeNoMtron, ClosedTimeNoMtron, ClosedTimeMtron],
+    // stop
+    [OpenNoTime, OpenNoTime, ClosedNoTimeNoMtron, ClosedNoTimeNoMtron, ClosedTimeNoMtron],
+    // start
+    [OpenNoTime, OpenTime, ClosedTimeMtron, ClosedTimeMtron, ClosedTimeMtron],
+    // one second
+    [OpenNoTime, OpenTime, ClosedNoTimeNoMtron, ClosedTimeNoMtron, ClosedTimeMtron],
+];
+
+pub fn next_state(state: State, input: Input) -> State {
+    TABLE[input.index()][state.index()]
+}
+
+// Everything that decides how the model behaves. The table isn't quite enough on
+// its own, there are a few rules about the time that go with it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Spec {
+    pub table: Table,
+    // Time given by start with no time set.
+    pub quick_start: usize,
+    // Time added by start while running.
+    pub extra_time: usize,
+    // Changing the time while running has no effect.
+    pub ignore_set_time_running: bool,
+    // When the time runs out, the magnetron stops.
+    pub stop_at_zero: bool,
+}
+
+impl Spec {
+    pub const REFERENCE: Spec = Spec {
+        table: TABLE,
+        quick_start: 30,
+        extra_time: 30,
+        ignore_set_time_running: true,
+        stop_at_zero: true,
+    };
+
+    pub fn next_state(&self, state: State, input: Input) -> State {
+        self.table[input.index()][state.index()]
+    }
+}
+
+// The reference microwave. Rather than being written by hand like the others, it
+// looks up the next state in the README table, so if the table is right, so is
+// this. The only thing it keeps beside the state is the time.
+//
+// It can also interpret some other spec, which is how we check the tests would
+// notice if the table were wrong.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Model {
+    spec: Spec,
+    state: State,
+    time: usize,
+}
+
+impl Model {
+    pub fn with_spec(spec: Spec) -> Self {
+        Model {
+            spec,
+            state: ClosedNoTimeNoMtron,
+            time: 0,
+        }
+    }
+
+    pub fn state(&self) -> State {
+        self.state
+    }
+
+    fn input(&mut self, input: Input) {
+        self.state = self.spec.next_state(self.state, input);
+        // Leaving the time behind would only be found again by a bad transition.
+        if self.state == OpenNoTime || self.state == ClosedNoTimeNoMtron {
+            self.time = 0;
+        }
+    }
+}
+
+impl MicrowaveOps for Model {
+    fn new() -> Self {
+        Model::with_spec(Spec::REFERENCE)
+    }
+
+    fn reset(&mut self) {
+        *self = Model::with_spec(self.spec);
+    }
+
+    fn tick(&mut self) {
+        if self.state == ClosedTimeMtron {
+            // A time of 0 can be set, so don't underflow.
+            self.time = self.time.saturating_sub(1);
+            if self.time == 0 && self.spec.stop_at_zero {
+                self.state = ClosedNoTimeNoMtron;
+                return;
+            }
+        }
+        self.input(Input::OneSecond);
+    }
+
+    fn magnetron_enabled(&self) -> bool {
+        self.state == ClosedTimeMtron
+    }
+
+    fn door_open(&self) -> bool {
+        self.state == OpenNoTime || self.state == OpenTime
+    }
+
+    fn time_remain(&self) -> usize {
+        match self.state {
+            OpenNoTime | ClosedNoTimeNoMtron => 0,
+            _ => self.time,
+        }
+    }
+
+    fn action_open_door(&mut self) {
+        self.input(Input::OpenDoor);
+    }
+
+    fn action_close_door(&mut self) {
+        self.input(Input::CloseDoor);
+    }
+
+    fn action_set_time(&mut self, t: usize) {
+        if !(self.spec.ignore_set_time_running && self.state == ClosedTimeMtron) {
+            self.time = t;
+        }
+        self.input(Input::SetTime);
+    }
+
+    fn action_start(&mut self) {
+        // Start with no time is a quick start, and while running adds some more.
+        match self.state {
+            ClosedNoTimeNoMtron => self.time = self.spec.quick_start,
+            ClosedTimeMtron => self.time += self.spec.extra_time,
+            _ => {}
+        }
+        self.input(Input::Start);
+    }
+
+    fn action_stop(&mut self) {
+        self.input(Input::Stop);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{next_state, Input, Model, State};
+    use crate::MicrowaveOps;
+
+    crate::microwave_conformance_tests!(Model);
+
+    #[test]
+    fn table() {
+        assert!(next_state(State::OpenTime, Input::CloseDoor) == State::ClosedTimeNoMtron);
+        assert!(next_state(State::ClosedTimeMtron, Input::Stop) == State::ClosedTimeNoMtron);
+        // There is no open and running state, and no way to start with the door open.
+        for s in State::ALL.iter() {
+            assert!(next_state(*s, Input::OpenDoor) != State::ClosedTimeMtron);
+        }
+        assert!(next_state(State::OpenTime, Input::Start) == State::OpenTime);
+    }
+
+    #[test]
+    fn stop_forgets_time() {
+        let mut mw = Model::new();
+        mw.action_set_time(45);
+        mw.action_stop();
+        assert!(mw.state() == State::ClosedNoTimeNoMtron);
+        assert!(mw.time_remain() == 0);
+        // Quick start from here is 30, not 45.
+        mw.action_start();
+        assert!(mw.time_remain() == 30);
+    }
+}
@@ -0,0 +1,209 @@
+use crate::{Event, TraceRecord};
+
+// Something that must hold over a whole trace, not just at each step. A property
+// sees every record in order, and says what's wrong as soon as it can tell.
+pub trait Property {
+    fn name(&self) -> &'static str;
+    fn observe(&mut self, r: &TraceRecord) -> Result<(), String>;
+}
+
+// Opening the door turns the magnetron off on the very same step - not a tick later.
+#[derive(Default)]
+pub struct DoorStopsMagnetron;
+
+impl Property for DoorStopsMagnetron {
+    fn name(&self) -> &'static str {
+        "door stops magnetron"
+    }
+
+    fn observe(&mut self, r: &TraceRecord) -> Result<(), String> {
+        if r.outputs.door_open && r.outputs.magnetron {
+            Err(format!("magnetron still on after {}", r.event))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+// Once started with time t, the magnetron is off within t ticks, unless start is
+// pressed again (which gives it a new t).
+#[derive(Default)]
+pub struct BoundedCook {
+    // Ticks left before the magnetron must be off, while it's on.
+    budget: Option<usize>,
+}
+
+impl Property for BoundedCook {
+    fn name(&self) -> &'static str {
+        "bounded cook"
+    }
+
+    fn observe(&mut self, r: &TraceRecord) -> Result<(), String> {
+        if !r.outputs.magnetron || r.event == Event::Reset {
+            self.budget = None;
+            return Ok(());
+        }
+        self.budget = match (self.budget, r.event) {
+            // It's come on, or start was pressed again.
+            (None, _) | (_, Event::Start) => Some(r.outputs.time_remain),
+            (Some(0), Event::Tick) | (Some(1), Event::Tick) => {
+                return Err("magnetron still on after its time ran out".to_string())
+            }
+            (Some(b), Event::Tick) => Some(b - 1),
+            (b, _) => b,
+        };
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct PropertyViolation {
+    pub step: usize,
+    pub property: &'static str,
+    pub event: Event,
+    pub reason: String,
+}
+
+// Watches a trace as it happens, checking a set of properties. Feed it from the
+// driver's observer, or from a recorded trace.
+pub struct Monitor {
+    properties: Vec<Box<dyn Property + Send>>,
+    step: usize,
+}
+
+impl Default for Monitor {
+    fn default() -> Self {
+        Monitor::new()
+    }
+}
+
+impl Monitor {
+    // All the properties we know every microwave must have.
+    pub fn new() -> Self {
+        Monitor::empty()
+            .with(DoorStopsMagnetron)
+            .with(BoundedCook::default())
+    }
+
+    pub fn empty() -> Self {
+        Monitor {
+            properties: Vec::new(),
+            step: 0,
+        }
+    }
+
+    pub fn with<P: Property + Send + 'static>(mut self, p: P) -> Self {
+        self.properties.push(Box::new(p));
+        self
+    }
+
+    pub fn observe(&mut self, r: &TraceRecord) -> Result<(), PropertyViolation> {
+        let step = self.step;
+        self.step += 1;
+        for p in self.properties.iter_mut() {
+            p.observe(r).map_err(|reason| PropertyViolation {
+                step,
+                property: p.name(),
+                event: r.event,
+                reason,
+            })?;
+        }
+        Ok(())
+    }
+
+    pub fn check(&mut self, trace: &[TraceRecord]) -> Result<(), PropertyViolation> {
+        trace.iter().try_for_each(|r| self.observe(r))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc::channel;
+    use std::time::Duration;
+
+    use crate::clock::VirtualClock;
+    use crate::driver::Driver;
+    use crate::model::Model;
+    use crate::monitor::Monitor;
+    use crate::{Event, MicrowaveOps, Outputs, TraceRecord};
+
+    fn record(event: Event, door_open: bool, magnetron: bool, time_remain: usize) -> TraceRecord {
+        TraceRecord {
+            event,
+            outputs: Outputs {
+                door_open,
+                magnetron,
+                time_remain,
+            },
+        }
+    }
+
+    #[test]
+    fn model_holds() {
+        let mut mw = Model::new();
+        let mut m = Monitor::new();
+        for e in [Event::SetTime(3), Event::Start, Event::Tick, Event::Start, Event::Tick].iter() {
+            assert!(m.observe(&TraceRecord::step(&mut mw, *e)).is_ok());
+        }
+        for _ in 0..40 {
+            assert!(m.observe(&TraceRecord::step(&mut mw, Event::Tick)).is_ok());
+        }
+        assert!(!mw.magnetron_enabled());
+    }
+
+    #[test]
+    fn runs_over() {
+        let trace = [
+            record(Event::SetTime(2), false, false, 2),
+            record(Event::Start, false, true, 2),
+            record(Event::Tick, false, true, 1),
+            // Forgot to stop at 0.
+            record(Event::Tick, false, true, 0),
+        ];
+        let v = Monitor::new().check(&trace).unwrap_err();
+        assert!(v.step == 3);
+        assert!(v.property == "bounded cook");
+
+        // Adding time with set_time while running is just as bad, as the budget is
+        // from when start was pressed.
+        let trace = [
+            record(Event::Start, false, true, 1),
+            record(Event::SetTime(60), false, true, 60),
+            record(Event::Tick, false, true, 59),
+        ];
+        assert!(Monitor::new().check(&trace).unwrap_err().step == 2);
+
+        // But start again is fine.
+        let trace = [
+            record(Event::Start, false, true, 1),
+            record(Event::Start, false, true, 31),
+            record(Event::Tick, false, true, 30),
+        ];
+        assert!(Monitor::new().check(&trace).is_ok());
+    }
+
+    #[test]
+    fn door() {
+        let trace = [
+            record(Event::Start, false, true, 30),
+            record(Event::OpenDoor, true, true, 30),
+        ];
+        let v = Monitor::new().check(&trace).unwrap_err();
+        assert!(v.property == "door stops magnetron");
+        assert!(v.reason == "magnetron still on after open_door");
+    }
+
+    #[test]
+    fn live_driver() {
+        let clock = VirtualClock::new();
+        let (tx, rx) = channel();
+        let mut d = Driver::with_clock(Model::new(), rx, clock.clone());
+        let mut m = Monitor::new();
+        tx.send(Event::SetTime(5)).unwrap();
+        tx.send(Event::Start).unwrap();
+        assert!(m.check(&d.poll()).is_ok());
+        clock.advance(Duration::from_secs(10));
+        assert!(m.check(&d.poll()).is_ok());
+        assert!(!d.microwave().magnetron_enabled());
+    }
+}
@@ -0,0 +1,135 @@
+use crate::model::{Input, Model, Spec, State};
+use crate::test_microwave;
+
+// A deliberately broken copy of the reference spec.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mutant {
+    pub name: String,
+    pub spec: Spec,
+}
+
+// Bugs that really happened, in rust_microwave_spaghetti. If the suite can't catch
+// these, it can't catch anything.
+pub fn historical_mutants() -> Vec<Mutant> {
+    let mut start_door_open = Spec::REFERENCE;
+    start_door_open.table[Input::Start.index()][State::OpenTime.index()] = State::ClosedTimeMtron;
+    start_door_open.table[Input::Start.index()][State::OpenNoTime.index()] = State::ClosedTimeMtron;
+
+    let mut runs_forever = Spec::REFERENCE;
+    runs_forever.stop_at_zero = false;
+
+    vec![
+        Mutant {
+            name: "start with the door open".to_string(),
+            spec: start_door_open,
+        },
+        Mutant {
+            name: "magnetron stays on at 0".to_string(),
+            spec: runs_forever,
+        },
+    ]
+}
+
+// Every single change we know how to make to the spec: each cell of the table
+// swapped to each other state, each guard dropped, and the +30s made +0.
+pub fn mutants() -> Vec<Mutant> {
+    let mut mutants = Vec::new();
+    for i in Input::ALL.iter() {
+        for s in State::ALL.iter() {
+            let target = Spec::REFERENCE.next_state(*s, *i);
+            for t in State::ALL.iter().filter(|t| **t != target) {
+                let mut spec = Spec::REFERENCE;
+                spec.table[i.index()][s.index()] = *t;
+                mutants.push(Mutant {
+                    name: format!("{} on {} -> {}", s.name(), i.name(), t.name()),
+                    spec,
+                });
+            }
+        }
+    }
+
+    let mut spec = Spec::REFERENCE;
+    spec.ignore_set_time_running = false;
+    mutants.push(Mutant {
+        name: "set time while running".to_string(),
+        spec,
+    });
+
+    let mut spec = Spec::REFERENCE;
+    spec.stop_at_zero = false;
+    mutants.push(Mutant {
+        name: "no stop at zero".to_string(),
+        spec,
+    });
+
+    let mut spec = Spec::REFERENCE;
+    spec.quick_start = 0;
+    mutants.push(Mutant {
+        name: "quick start +0".to_string(),
+        spec,
+    });
+
+    let mut spec = Spec::REFERENCE;
+    spec.extra_time = 0;
+    mutants.push(Mutant {
+        name: "start while running +0".to_string(),
+        spec,
+    });
+
+    mutants
+}
+
+#[derive(Debug, Default)]
+pub struct MutationReport {
+    pub killed: Vec<String>,
+    pub survived: Vec<String>,
+}
+
+impl MutationReport {
+    pub fn score(&self) -> (usize, usize) {
+        (self.killed.len(), self.killed.len() + self.survived.len())
+    }
+}
+
+// A mutant is killed if the conformance suite fails against it. Any that survive
+// are changes to the spec that the tests can't see.
+pub fn run_mutants(mutants: &[Mutant]) -> MutationReport {
+    let mut report = MutationReport::default();
+    for m in mutants.iter() {
+        let mut mw = Model::with_spec(m.spec);
+        if test_microwave(&mut mw).passed() {
+            report.survived.push(m.name.clone());
+        } else {
+            report.killed.push(m.name.clone());
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::Model;
+    use crate::mutation::{historical_mutants, mutants, run_mutants};
+    use crate::{test_microwave, MicrowaveOps};
+
+    #[test]
+    fn reference_survives() {
+        assert!(test_microwave(&mut Model::new()).passed());
+    }
+
+    #[test]
+    fn historical_bugs_killed() {
+        let report = run_mutants(&historical_mutants());
+        assert!(report.survived.is_empty());
+    }
+
+    #[test]
+    fn all_mutants() {
+        let all = mutants();
+        // 4 wrong answers for each of the 30 cells, and 4 rule changes.
+        assert!(all.len() == 124);
+        let report = run_mutants(&all);
+        println!("survivors: {:#?}", report.survived);
+        assert!(report.survived.is_empty());
+    }
+}
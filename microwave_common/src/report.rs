@@ -0,0 +1,172 @@
+use std::fmt;
+
+use crate::{Event, Outputs};
+
+// What happened on one line of a scenario.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StepReport {
+    pub line: usize,
+    pub event: Event,
+    // None if the scenario didn't say what to expect here.
+    pub expected: Option<Outputs>,
+    pub actual: Outputs,
+}
+
+impl StepReport {
+    // The magnetron must never run with the door open, whatever the scenario expects.
+    pub fn unsafe_combo(&self) -> bool {
+        self.actual.door_open && self.actual.magnetron
+    }
+
+    pub fn passed(&self) -> bool {
+        !self.unsafe_combo() && self.expected.map(|e| e == self.actual).unwrap_or(true)
+    }
+}
+
+// Every step of one scenario. The steps are also the event history, so a failure
+// can be shown along with how we got there.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GroupReport {
+    pub name: String,
+    pub steps: Vec<StepReport>,
+}
+
+impl GroupReport {
+    pub fn passed(&self) -> bool {
+        self.steps.iter().all(|s| s.passed())
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = &StepReport> {
+        self.steps.iter().filter(|s| !s.passed())
+    }
+}
+
+impl fmt::Display for GroupReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.passed() {
+            return writeln!(f, "{}: ok ({} steps)", self.name, self.steps.len());
+        }
+        writeln!(f, "{}: FAILED", self.name)?;
+        for (i, s) in self.steps.iter().enumerate() {
+            if s.passed() {
+                continue;
+            }
+            writeln!(f, "  line {}: {}", s.line, s.event)?;
+            if let Some(e) = s.expected {
+                writeln!(f, "    expected: {}", e)?;
+            }
+            writeln!(f, "    actual:   {}", s.actual)?;
+            if s.unsafe_combo() {
+                writeln!(f, "    the magnetron is on with the door open!")?;
+            }
+            let history: Vec<String> = self.steps[..=i].iter().map(|s| s.event.to_string()).collect();
+            writeln!(f, "    history:  {}", history.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+// The result of running every scenario against an implementation.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ConformanceReport {
+    pub groups: Vec<GroupReport>,
+}
+
+impl ConformanceReport {
+    pub fn passed(&self) -> bool {
+        self.groups.iter().all(|g| g.passed())
+    }
+
+    pub fn failed_groups(&self) -> impl Iterator<Item = &GroupReport> {
+        self.groups.iter().filter(|g| !g.passed())
+    }
+}
+
+impl fmt::Display for ConformanceReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for g in self.groups.iter() {
+            write!(f, "{}", g)?;
+        }
+        if self.passed() {
+            writeln!(f, "✨ Your implementation passes! ✨")
+        } else {
+            writeln!(
+                f,
+                "{} of {} groups failed",
+                self.failed_groups().count(),
+                self.groups.len()
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::report::{ConformanceReport, GroupReport, StepReport};
+    use crate::{Event, Outputs};
+
+    fn outputs(door_open: bool, magnetron: bool, time_remain: usize) -> Outputs {
+        Outputs {
+            door_open,
+            magnetron,
+            time_remain,
+        }
+    }
+
+    #[test]
+    fn report() {
+        let ok = GroupReport {
+            name: "good".to_string(),
+            steps: vec![StepReport {
+                line: 1,
+                event: Event::OpenDoor,
+                expected: Some(outputs(true, false, 0)),
+                actual: outputs(true, false, 0),
+            }],
+        };
+        let bad = GroupReport {
+            name: "bad".to_string(),
+            steps: vec![
+                StepReport {
+                    line: 2,
+                    event: Event::SetTime(30),
+                    expected: None,
+                    actual: outputs(false, false, 30),
+                },
+                StepReport {
+                    line: 3,
+                    event: Event::Start,
+                    expected: Some(outputs(false, true, 30)),
+                    actual: outputs(false, false, 30),
+                },
+                // Unsafe, even though nothing was expected.
+                StepReport {
+                    line: 4,
+                    event: Event::OpenDoor,
+                    expected: None,
+                    actual: outputs(true, true, 30),
+                },
+            ],
+        };
+        assert!(ok.passed());
+        assert!(!bad.passed());
+        assert!(bad.failures().map(|s| s.line).collect::<Vec<_>>() == vec![3, 4]);
+
+        let r = ConformanceReport { groups: vec![ok, bad] };
+        assert!(!r.passed());
+        assert!(
+            r.to_string()
+                == "good: ok (1 steps)\n\
+                    bad: FAILED\n  \
+                    line 3: start\n    \
+                    expected: closed on 30\n    \
+                    actual:   closed off 30\n    \
+                    history:  set_time 30, start\n  \
+                    line 4: open_door\n    \
+                    actual:   open on 30\n    \
+                    the magnetron is on with the door open!\n    \
+                    history:  set_time 30, start, open_door\n\
+                    1 of 2 groups failed\n"
+        );
+    }
+}
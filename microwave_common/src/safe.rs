@@ -0,0 +1,160 @@
+use crate::{Event, MicrowaveOps};
+
+// A microwave we don't trust - a student's, or one we're still writing. After every
+// event we check the one thing that must never happen: the magnetron on with the
+// door open. If it does, we shut down and stay shut down, refusing to start, until
+// reset. Shutting down means telling the inner microwave to stop, after every event
+// if it has to be, and ignoring its magnetron output in case it doesn't listen. It
+// is still told about everything else so it can be inspected.
+pub struct SafeMicrowave<T: MicrowaveOps> {
+    inner: T,
+    violation: Option<Event>,
+}
+
+impl<T: MicrowaveOps> SafeMicrowave<T> {
+    pub fn wrap(inner: T) -> Self {
+        SafeMicrowave {
+            inner,
+            violation: None,
+        }
+    }
+
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    // The event after which the inner microwave was unsafe, if it ever was.
+    pub fn violation(&self) -> Option<Event> {
+        self.violation
+    }
+
+    fn step(&mut self, event: Event) {
+        if self.violation.is_some() && event == Event::Start {
+            return;
+        }
+        event.apply(&mut self.inner);
+        if self.violation.is_none() && self.inner.door_open() && self.inner.magnetron_enabled() {
+            self.violation = Some(event);
+        }
+        // Only while it's running, as stop with no cooking going on clears the time.
+        if self.violation.is_some() && self.inner.magnetron_enabled() {
+            self.inner.action_stop();
+        }
+    }
+}
+
+impl<T: MicrowaveOps> MicrowaveOps for SafeMicrowave<T> {
+    fn new() -> Self {
+        SafeMicrowave::wrap(T::new())
+    }
+
+    fn reset(&mut self) {
+        self.violation = None;
+        self.step(Event::Reset);
+    }
+
+    fn tick(&mut self) {
+        self.step(Event::Tick);
+    }
+
+    fn magnetron_enabled(&self) -> bool {
+        self.violation.is_none() && self.inner.magnetron_enabled()
+    }
+
+    fn door_open(&self) -> bool {
+        self.inner.door_open()
+    }
+
+    fn time_remain(&self) -> usize {
+        self.inner.time_remain()
+    }
+
+    fn action_open_door(&mut self) {
+        self.step(Event::OpenDoor);
+    }
+
+    fn action_close_door(&mut self) {
+        self.step(Event::CloseDoor);
+    }
+
+    fn action_set_time(&mut self, t: usize) {
+        self.step(Event::SetTime(t));
+    }
+
+    fn action_start(&mut self) {
+        self.step(Event::Start);
+    }
+
+    fn action_stop(&mut self) {
+        self.step(Event::Stop);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::faulty::{Faults, Faulty};
+    use crate::model::Model;
+    use crate::safe::SafeMicrowave;
+    use crate::{microwave_conformance_tests, Event, MicrowaveOps};
+
+    // A correct microwave never trips it.
+    microwave_conformance_tests!(SafeMicrowave<Model>);
+
+    // Forgot to check the door, like the spaghetti microwave's first bug. It also
+    // won't stop, no matter what.
+    fn door_blind() -> SafeMicrowave<Faulty> {
+        SafeMicrowave::wrap(Faulty::with(Faults {
+            ignores_door: true,
+            ignores_stop: true,
+            ..Faults::default()
+        }))
+    }
+
+    #[test]
+    fn latches() {
+        let mut mw = door_blind();
+        mw.action_start();
+        assert!(mw.magnetron_enabled());
+        assert!(mw.violation().is_none());
+
+        mw.action_open_door();
+        assert!(mw.violation() == Some(Event::OpenDoor));
+        assert!(!mw.magnetron_enabled());
+        // It was told to stop, but won't - so it would still be running, if we let it.
+        assert!(mw.inner().magnetron_enabled());
+
+        // Closing the door doesn't make it trustworthy again.
+        mw.action_close_door();
+        assert!(!mw.magnetron_enabled());
+        mw.action_start();
+        assert!(!mw.magnetron_enabled());
+        assert!(mw.violation() == Some(Event::OpenDoor));
+
+        mw.reset();
+        assert!(mw.violation().is_none());
+        mw.action_open_door();
+        mw.action_start();
+        assert!(mw.violation() == Some(Event::Start));
+        assert!(!mw.magnetron_enabled());
+
+        // One that does stop when told to is actually stopped, time and all.
+        let mut mw = SafeMicrowave::wrap(Faulty::with(Faults {
+            ignores_door: true,
+            ..Faults::default()
+        }));
+        mw.action_start();
+        mw.action_open_door();
+        assert!(mw.violation() == Some(Event::OpenDoor));
+        assert!(!mw.inner().magnetron_enabled());
+        assert!(mw.inner().time_remain() == 30);
+        mw.action_close_door();
+        mw.action_start();
+        mw.tick();
+        assert!(!mw.inner().magnetron_enabled());
+        assert!(mw.inner().time_remain() == 30);
+    }
+}
@@ -0,0 +1,196 @@
+use std::fmt;
+
+use crate::report::{GroupReport, StepReport};
+use crate::{Event, MicrowaveOps, Outputs};
+
+// The scenarios in microwave_common/scenarios, as (name, text). See the README there
+// for the format.
+include!(concat!(env!("OUT_DIR"), "/scenarios.rs"));
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Step {
+    // Something to print, to encourage whoever is running the tests.
+    Say(String),
+    // Apply an event, then check the outputs if we were told what to expect.
+    Event {
+        line: usize,
+        event: Event,
+        expect: Option<Outputs>,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Scenario {
+    pub name: String,
+    pub steps: Vec<Step>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ParseError {
+    pub line: usize,
+    pub reason: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.reason)
+    }
+}
+
+fn parse_outputs(s: &str) -> Result<Outputs, String> {
+    let words: Vec<&str> = s.split_whitespace().collect();
+    let (door, mtron, time) = match words.as_slice() {
+        [d, m, t] => (d, m, t),
+        _ => return Err(format!("expected '<door> <magnetron> <time>', found '{}'", s.trim())),
+    };
+    let door_open = match *door {
+        "open" => true,
+        "closed" => false,
+        d => return Err(format!("door must be open or closed, found '{}'", d)),
+    };
+    let magnetron = match *mtron {
+        "on" => true,
+        "off" => false,
+        m => return Err(format!("magnetron must be on or off, found '{}'", m)),
+    };
+    let time_remain = time
+        .parse()
+        .map_err(|_| format!("invalid time '{}'", time))?;
+    Ok(Outputs {
+        door_open,
+        magnetron,
+        time_remain,
+    })
+}
+
+impl Scenario {
+    pub fn parse(name: &str, text: &str) -> Result<Self, ParseError> {
+        let mut steps = Vec::new();
+        for (i, l) in text.lines().enumerate() {
+            let line = i + 1;
+            let l = l.trim();
+            if l.is_empty() || l.starts_with('#') {
+                continue;
+            }
+            if let Some(msg) = l.strip_prefix("say ") {
+                steps.push(Step::Say(msg.trim().to_string()));
+                continue;
+            }
+            let (event, expect) = match l.find("=>") {
+                Some(i) => (&l[..i], Some(&l[i + 2..])),
+                None => (l, None),
+            };
+            let event = event.parse().map_err(|reason| ParseError { line, reason })?;
+            let expect = match expect {
+                Some(e) => Some(parse_outputs(e).map_err(|reason| ParseError { line, reason })?),
+                None => None,
+            };
+            steps.push(Step::Event {
+                line,
+                event,
+                expect,
+            });
+        }
+        Ok(Scenario {
+            name: name.to_string(),
+            steps,
+        })
+    }
+
+    // The scenarios every microwave must pass.
+    pub fn builtin() -> Vec<Scenario> {
+        SCENARIOS
+            .iter()
+            .map(|(name, text)| {
+                Scenario::parse(name, text)
+                    .unwrap_or_else(|e| panic!("scenarios/{}: {}", name, e))
+            })
+            .collect()
+    }
+}
+
+// Run every step, recording what happened rather than stopping at the first thing
+// that's wrong.
+pub fn run_scenario<T: MicrowaveOps>(mw: &mut T, scenario: &Scenario) -> GroupReport {
+    let mut steps = Vec::new();
+    for step in scenario.steps.iter() {
+        match step {
+            Step::Say(msg) => println!("{}", msg),
+            Step::Event { line, event, expect } => {
+                event.apply(mw);
+                steps.push(StepReport {
+                    line: *line,
+                    event: *event,
+                    expected: *expect,
+                    actual: Outputs::of(mw),
+                });
+            }
+        }
+    }
+    GroupReport {
+        name: scenario.name.clone(),
+        steps,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scenario::{Scenario, Step, SCENARIOS};
+    use crate::{Event, Outputs};
+
+    #[test]
+    fn builtin() {
+        // Named after the file, without the number that orders them.
+        assert!(SCENARIOS[0].0 == "door_open_start_refusal");
+        assert!(SCENARIOS.iter().all(|(n, _)| !n.starts_with(char::is_numeric)));
+        // They all parse.
+        assert!(Scenario::builtin().len() == SCENARIOS.len());
+    }
+
+    #[test]
+    fn parse() {
+        let s = Scenario::parse(
+            "example",
+            "# A comment\n\
+             say Hello!\n\
+             reset\n\
+             \n\
+             set_time 2   => closed off 2\n\
+             start=>closed on 2\n",
+        )
+        .unwrap();
+        assert!(
+            s.steps
+                == vec![
+                    Step::Say("Hello!".to_string()),
+                    Step::Event { line: 3, event: Event::Reset, expect: None },
+                    Step::Event {
+                        line: 5,
+                        event: Event::SetTime(2),
+                        expect: Some(Outputs { door_open: false, magnetron: false, time_remain: 2 }),
+                    },
+                    Step::Event {
+                        line: 6,
+                        event: Event::Start,
+                        expect: Some(Outputs { door_open: false, magnetron: true, time_remain: 2 }),
+                    },
+                ]
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        let e = Scenario::parse("bad", "reset\nbake 20\n").unwrap_err();
+        assert!(e.line == 2);
+        assert!(e.to_string() == "line 2: unknown event 'bake 20'");
+
+        let e = Scenario::parse("bad", "set_time soon\n").unwrap_err();
+        assert!(e.to_string() == "line 1: invalid time 'soon'");
+
+        let e = Scenario::parse("bad", "start => ajar on 30\n").unwrap_err();
+        assert!(e.to_string() == "line 1: door must be open or closed, found 'ajar'");
+
+        let e = Scenario::parse("bad", "start => closed on\n").unwrap_err();
+        assert!(e.line == 1);
+    }
+}
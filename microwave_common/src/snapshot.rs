@@ -0,0 +1,94 @@
+use core::convert::TryFrom;
+
+use crate::MicrowaveOps;
+
+// A snapshot is a fixed thirteen bytes:
+//
+//     | version | state | time (u64, little endian) | thermal | cavity temp (u16, little endian) |
+//
+// The state tags are shared by every implementation (they match the C enum), so
+// a snapshot taken by one microwave can be restored by any other.
+//
+// Version 2 added the thermal part - a short power cut doesn't cool the cavity,
+// so an overheated microwave has to come back still locked out.
+pub const SNAPSHOT_VERSION: u8 = 2;
+pub const SNAPSHOT_LEN: usize = 13;
+
+pub const TAG_CLOSED_NOTIME_NOMTRON: u8 = 0;
+pub const TAG_OPEN_TIME: u8 = 1;
+pub const TAG_OPEN_NOTIME: u8 = 2;
+pub const TAG_CLOSED_TIME_NOMTRON: u8 = 3;
+pub const TAG_CLOSED_TIME_MTRON: u8 = 4;
+
+// Microwaves without a thermal model leave this as the default - not overheated,
+// and a cavity temp of 0, IE never measured.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ThermalSnapshot {
+    pub overheated: bool,
+    pub cavity_temp: u16,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SnapshotError {
+    // Not SNAPSHOT_LEN bytes.
+    Length,
+    // A snapshot from a version we don't understand.
+    Version,
+    // An unknown state, a time where there can be none, or a thermal byte that
+    // isn't 0 or 1.
+    Corrupt,
+}
+
+// Persist a microwave so it survives a power cut.
+//
+// The policy on restore is that a microwave that was running comes back *stopped*
+// with its time preserved. Nobody wants a microwave that turns itself on when the
+// power comes back.
+pub trait SnapshotOps: MicrowaveOps + Sized {
+    fn snapshot(&self) -> [u8; SNAPSHOT_LEN];
+    fn restore(snapshot: &[u8]) -> Result<Self, SnapshotError>;
+}
+
+pub fn encode_snapshot(tag: u8, time: usize, thermal: ThermalSnapshot) -> [u8; SNAPSHOT_LEN] {
+    let mut buf = [0; SNAPSHOT_LEN];
+    buf[0] = SNAPSHOT_VERSION;
+    buf[1] = tag;
+    buf[2..10].copy_from_slice(&(time as u64).to_le_bytes());
+    buf[10] = thermal.overheated as u8;
+    buf[11..].copy_from_slice(&thermal.cavity_temp.to_le_bytes());
+    buf
+}
+
+// Returns the state tag, time and thermal state. The tag is checked, and that
+// the time is zero for the states that have no time.
+pub fn decode_snapshot(snapshot: &[u8]) -> Result<(u8, usize, ThermalSnapshot), SnapshotError> {
+    // Version first, so an old snapshot says so rather than being the wrong length.
+    match snapshot.first() {
+        None => return Err(SnapshotError::Length),
+        Some(&SNAPSHOT_VERSION) => {}
+        Some(_) => return Err(SnapshotError::Version),
+    }
+    if snapshot.len() != SNAPSHOT_LEN {
+        return Err(SnapshotError::Length);
+    }
+    let mut time = [0; 8];
+    time.copy_from_slice(&snapshot[2..10]);
+    let time = usize::try_from(u64::from_le_bytes(time)).map_err(|_| SnapshotError::Corrupt)?;
+
+    let thermal = ThermalSnapshot {
+        overheated: match snapshot[10] {
+            0 => false,
+            1 => true,
+            _ => return Err(SnapshotError::Corrupt),
+        },
+        cavity_temp: u16::from_le_bytes([snapshot[11], snapshot[12]]),
+    };
+
+    match (snapshot[1], time) {
+        (TAG_CLOSED_NOTIME_NOMTRON, 0) | (TAG_OPEN_NOTIME, 0) => Ok((snapshot[1], 0, thermal)),
+        (TAG_OPEN_TIME, t) | (TAG_CLOSED_TIME_NOMTRON, t) | (TAG_CLOSED_TIME_MTRON, t) => {
+            Ok((snapshot[1], t, thermal))
+        }
+        _ => Err(SnapshotError::Corrupt),
+    }
+}
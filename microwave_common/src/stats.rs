@@ -0,0 +1,267 @@
+use crate::{Event, MicrowaveOps, Outputs};
+
+// Counters over the whole life of a microwave. Unlike the microwave itself, these
+// survive a reset.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Stats {
+    pub cycles_started: u64,
+    // The time ran out.
+    pub cycles_completed: u64,
+    // Stopped, or the door opened, before the time ran out.
+    pub cycles_aborted: u64,
+    // Seconds the magnetron has been on for.
+    pub magnetron_ticks: u64,
+    pub door_opens: u64,
+    // Start pressed with no time set since the last cook - either to begin, or to
+    // add more.
+    pub quick_starts: u64,
+}
+
+// Wraps a microwave and counts what it does. Like the audit log, we only watch the
+// outputs, so this works with any implementation.
+pub struct Metered<T: MicrowaveOps> {
+    inner: T,
+    stats: Stats,
+    // Magnetron ticks at the last service.
+    serviced_at: u64,
+    service_life: Option<u64>,
+    // A time has been set since the last clearing stop or finished cook, so a
+    // start now is using that time and isn't a quick start.
+    time_set: bool,
+}
+
+impl<T: MicrowaveOps> Metered<T> {
+    pub fn wrap(inner: T) -> Self {
+        Metered {
+            inner,
+            stats: Stats::default(),
+            serviced_at: 0,
+            service_life: None,
+            time_set: false,
+        }
+    }
+
+    // Magnetrons wear out. After this many seconds of use, service_due is set.
+    pub fn with_service_life(mut self, ticks: u64) -> Self {
+        self.service_life = Some(ticks);
+        self
+    }
+
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    pub fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    // Seconds of magnetron use since it was last serviced.
+    pub fn magnetron_ticks_since_service(&self) -> u64 {
+        self.stats.magnetron_ticks - self.serviced_at
+    }
+
+    // Only says whether it's due. To see how far past its service life the
+    // magnetron is, compare magnetron_ticks_since_service with the life instead.
+    pub fn service_due(&self) -> bool {
+        match self.service_life {
+            Some(life) => self.magnetron_ticks_since_service() >= life,
+            None => false,
+        }
+    }
+
+    // A new magnetron has been fitted. The lifetime stats are kept.
+    pub fn service(&mut self) {
+        self.serviced_at = self.stats.magnetron_ticks;
+    }
+
+    fn step(&mut self, event: Event) {
+        let before = Outputs::of(&self.inner);
+        event.apply(&mut self.inner);
+        let after = Outputs::of(&self.inner);
+        let s = &mut self.stats;
+
+        if event == Event::Tick && before.magnetron {
+            s.magnetron_ticks += 1;
+        }
+        if after.door_open && !before.door_open {
+            s.door_opens += 1;
+        }
+        // Go by what was pressed, not the time showing - set_time(0) then start
+        // is someone's choice of time, not a quick start.
+        if event == Event::Start && after.magnetron && !self.time_set {
+            s.quick_starts += 1;
+        }
+        match event {
+            // Set time is ignored while it's running.
+            Event::SetTime(_) if !before.magnetron => self.time_set = true,
+            // A stop while running only pauses it, and the time set still stands.
+            Event::Stop if !before.magnetron => self.time_set = false,
+            Event::Reset => self.time_set = false,
+            Event::Tick if before.magnetron && !after.magnetron => self.time_set = false,
+            _ => {}
+        }
+        if after.magnetron && !before.magnetron {
+            s.cycles_started += 1;
+        }
+        if before.magnetron && !after.magnetron {
+            if event == Event::Tick && after.time_remain == 0 {
+                s.cycles_completed += 1;
+            } else {
+                s.cycles_aborted += 1;
+            }
+        }
+    }
+}
+
+impl<T: MicrowaveOps> MicrowaveOps for Metered<T> {
+    fn new() -> Self {
+        Metered::wrap(T::new())
+    }
+
+    fn reset(&mut self) {
+        self.step(Event::Reset);
+    }
+
+    fn tick(&mut self) {
+        self.step(Event::Tick);
+    }
+
+    fn magnetron_enabled(&self) -> bool {
+        self.inner.magnetron_enabled()
+    }
+
+    fn door_open(&self) -> bool {
+        self.inner.door_open()
+    }
+
+    fn time_remain(&self) -> usize {
+        self.inner.time_remain()
+    }
+
+    fn action_open_door(&mut self) {
+        self.step(Event::OpenDoor);
+    }
+
+    fn action_close_door(&mut self) {
+        self.step(Event::CloseDoor);
+    }
+
+    fn action_set_time(&mut self, t: usize) {
+        self.step(Event::SetTime(t));
+    }
+
+    fn action_start(&mut self) {
+        self.step(Event::Start);
+    }
+
+    fn action_stop(&mut self) {
+        self.step(Event::Stop);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::Model;
+    use crate::stats::{Metered, Stats};
+    use crate::MicrowaveOps;
+
+    #[test]
+    fn counts() {
+        let mut mw = Metered::<Model>::new();
+        // Quick start, then add some more, and let it finish.
+        mw.action_start();
+        mw.action_start();
+        for _ in 0..60 {
+            mw.tick();
+        }
+        // Set a time, and change our minds part way.
+        mw.action_set_time(10);
+        mw.action_start();
+        mw.tick();
+        mw.action_open_door();
+        // Ticks with the door open don't count.
+        mw.tick();
+        mw.action_close_door();
+        mw.action_start();
+        mw.tick();
+        mw.action_stop();
+        // And they survive a reset.
+        mw.reset();
+        mw.action_start();
+        mw.reset();
+
+        assert!(
+            *mw.stats()
+                == Stats {
+                    cycles_started: 4,
+                    cycles_completed: 1,
+                    cycles_aborted: 3,
+                    magnetron_ticks: 62,
+                    door_opens: 1,
+                    quick_starts: 3,
+                }
+        );
+    }
+
+    #[test]
+    fn quick_starts() {
+        let mut mw = Metered::<Model>::new();
+        // A time of 0 was still set, so this isn't a quick start.
+        mw.action_set_time(0);
+        mw.action_start();
+        mw.tick();
+        assert!(mw.stats().quick_starts == 0);
+
+        // Neither is carrying on after a pause, even once the time has run down.
+        mw.action_set_time(2);
+        mw.action_start();
+        mw.tick();
+        mw.action_stop();
+        mw.action_start();
+        // Or adding more to a time that was set.
+        mw.action_start();
+        assert!(mw.stats().quick_starts == 0);
+
+        // Finishing forgets the time that was set.
+        for _ in 0..31 {
+            mw.tick();
+        }
+        assert!(!mw.magnetron_enabled());
+        mw.action_start();
+        mw.action_start();
+        assert!(mw.stats().quick_starts == 2);
+
+        // So does a stop that clears it. A start refused with the door open
+        // isn't counted at all.
+        mw.action_stop();
+        mw.action_stop();
+        mw.action_open_door();
+        mw.action_start();
+        mw.action_close_door();
+        mw.action_start();
+        assert!(mw.stats().quick_starts == 3);
+    }
+
+    #[test]
+    fn service_life() {
+        let mut mw = Metered::wrap(Model::new()).with_service_life(45);
+        mw.action_start();
+        for _ in 0..30 {
+            mw.tick();
+        }
+        assert!(!mw.service_due());
+        mw.action_start();
+        for _ in 0..15 {
+            mw.tick();
+        }
+        assert!(mw.magnetron_ticks_since_service() == 45);
+        assert!(mw.service_due());
+
+        mw.service();
+        assert!(!mw.service_due());
+        assert!(mw.stats().magnetron_ticks == 45);
+
+        // Without a service life, it's never due.
+        assert!(!Metered::wrap(Model::new()).service_due());
+    }
+}
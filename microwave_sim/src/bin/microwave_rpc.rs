@@ -0,0 +1,55 @@
+use std::env;
+use std::io;
+use std::os::unix::net::UnixListener;
+use std::process;
+
+use microwave_common::clock::SystemClock;
+use microwave_common::safe::SafeMicrowave;
+use microwave_common::MicrowaveOps;
+use microwave_sim::rpc::serve;
+use microwave_sim::Implementation;
+
+const USAGE: &str = "usage: microwave_rpc [--impl spaghetti|simple|typed|c] [--socket <path>]";
+const DEFAULT_SOCKET: &str = "microwave.sock";
+
+fn run<T: MicrowaveOps + Send + 'static>(listener: UnixListener) -> io::Result<()> {
+    serve(listener, SafeMicrowave::<T>::new(), SystemClock::new())
+}
+
+fn usage(e: &str) -> ! {
+    eprintln!("{}\n{}", e, USAGE);
+    process::exit(2);
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let mut imp = Implementation::Simple;
+    let mut socket = DEFAULT_SOCKET.to_string();
+    for pair in args.chunks(2) {
+        match pair {
+            [flag, name] if flag == "--impl" => imp = name.parse().unwrap_or_else(|e: String| usage(&e)),
+            [flag, path] if flag == "--socket" => socket = path.clone(),
+            _ => usage("unknown argument"),
+        }
+    }
+
+    let listener = match UnixListener::bind(&socket) {
+        Ok(l) => l,
+        Err(e) => {
+            // Most likely a socket left behind by a previous run.
+            eprintln!("can't listen on {}: {} (remove it if it's stale)", socket, e);
+            process::exit(1);
+        }
+    };
+    println!("🍲 {:?} microwave on {}", imp, socket);
+    let r = match imp {
+        Implementation::Spaghetti => run::<rust_microwave_spaghetti::Microwave>(listener),
+        Implementation::Simple => run::<rust_microwave_simple::Microwave>(listener),
+        Implementation::Typed => run::<rust_microwave_typed::wrapper::TypedMicrowave>(listener),
+        Implementation::C => run::<c_microwave_simple::Microwave>(listener),
+    };
+    if let Err(e) = r {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
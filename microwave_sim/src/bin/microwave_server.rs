@@ -0,0 +1,56 @@
+use std::env;
+use std::io;
+use std::net::TcpListener;
+use std::process;
+
+use microwave_common::clock::SystemClock;
+use microwave_common::safe::SafeMicrowave;
+use microwave_common::MicrowaveOps;
+use microwave_sim::server::serve;
+use microwave_sim::Implementation;
+
+const USAGE: &str = "usage: microwave_server [--impl spaghetti|simple|typed|c] [--port <port>]";
+const DEFAULT_PORT: u16 = 7878;
+
+fn run<T: MicrowaveOps + Send + 'static>(listener: TcpListener) -> io::Result<()> {
+    serve(listener, SafeMicrowave::<T>::new(), SystemClock::new())
+}
+
+fn usage(e: &str) -> ! {
+    eprintln!("{}\n{}", e, USAGE);
+    process::exit(2);
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let mut imp = Implementation::Simple;
+    let mut port = DEFAULT_PORT;
+    for pair in args.chunks(2) {
+        match pair {
+            [flag, name] if flag == "--impl" => imp = name.parse().unwrap_or_else(|e: String| usage(&e)),
+            [flag, p] if flag == "--port" => {
+                port = p.parse().unwrap_or_else(|_| usage(&format!("invalid port '{}'", p)))
+            }
+            _ => usage("unknown argument"),
+        }
+    }
+
+    let listener = match TcpListener::bind(("127.0.0.1", port)) {
+        Ok(l) => l,
+        Err(e) => {
+            eprintln!("can't listen on port {}: {}", port, e);
+            process::exit(1);
+        }
+    };
+    println!("🍲 {:?} microwave on 127.0.0.1:{}", imp, port);
+    let r = match imp {
+        Implementation::Spaghetti => run::<rust_microwave_spaghetti::Microwave>(listener),
+        Implementation::Simple => run::<rust_microwave_simple::Microwave>(listener),
+        Implementation::Typed => run::<rust_microwave_typed::wrapper::TypedMicrowave>(listener),
+        Implementation::C => run::<c_microwave_simple::Microwave>(listener),
+    };
+    if let Err(e) = r {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
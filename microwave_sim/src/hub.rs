@@ -0,0 +1,206 @@
+use std::io::Write;
+use std::sync::mpsc::{channel, Sender};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use microwave_common::clock::Clock;
+use microwave_common::driver::Driver;
+use microwave_common::{Event, MicrowaveOps, Outputs, TraceRecord};
+
+// Told about every change to the outputs. Returns false once it doesn't want to
+// hear any more, IE its client hung up. It's called on the driver's thread with the
+// hub locked, so it mustn't block - put what it wants to say in an outbox.
+pub type Subscriber = Box<dyn FnMut(&Outputs) -> bool + Send>;
+
+// How long a client has to take a line before we give up on it. Set it on the
+// socket an outbox writes to.
+pub const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Lines waiting to go out to one client. Queuing one never waits, as they're written
+// on a thread of the client's own - so a client that stops reading only holds up
+// itself, never the driver or anyone else.
+pub type Outbox = Sender<String>;
+
+// Start writing lines to a client, in the order they're queued. Once a write fails,
+// IE the client hung up or took longer than WRITE_TIMEOUT, the rest are thrown away
+// and hang_up is called so whoever is reading from the client stops too.
+pub fn outbox<W, F>(mut w: W, hang_up: F) -> Outbox
+where
+    W: Write + Send + 'static,
+    F: FnOnce() + Send + 'static,
+{
+    let (tx, rx) = channel::<String>();
+    thread::spawn(move || {
+        for line in rx.iter() {
+            if writeln!(w, "{}", line).is_err() {
+                break;
+            }
+        }
+        // Anything queued from now on fails, so subscribers know to go.
+        drop(rx);
+        hang_up();
+    });
+    tx
+}
+
+struct State {
+    outputs: Outputs,
+    // How many sent events the driver has applied.
+    applied: u64,
+    subscribers: Vec<(usize, Subscriber)>,
+    next_id: usize,
+}
+
+// One microwave, run by a driver on its own thread, shared by everyone connected
+// to a server. Events go in from any thread, and every change to the outputs is
+// passed out to the subscribers.
+pub struct Hub {
+    // The sender, and how many events have been sent through it.
+    events: Mutex<(Sender<Event>, u64)>,
+    state: Mutex<State>,
+    applied: Condvar,
+}
+
+impl Hub {
+    pub fn start<T, C>(mw: T, clock: C) -> Arc<Hub>
+    where
+        T: MicrowaveOps + Send + 'static,
+        C: Clock + Send + 'static,
+    {
+        let (tx, rx) = channel();
+        let hub = Arc::new(Hub {
+            events: Mutex::new((tx, 0)),
+            state: Mutex::new(State {
+                outputs: Outputs::of(&mw),
+                applied: 0,
+                subscribers: Vec::new(),
+                next_id: 0,
+            }),
+            applied: Condvar::new(),
+        });
+        let driver = Driver::with_clock(mw, rx, clock);
+        let h = hub.clone();
+        thread::spawn(move || driver.run(|r| h.observe(r)));
+        hub
+    }
+
+    fn observe(&self, r: &TraceRecord) {
+        let mut s = self.state.lock().unwrap();
+        if r.event != Event::Tick {
+            s.applied += 1;
+        }
+        if s.outputs != r.outputs {
+            s.outputs = r.outputs;
+            let outputs = s.outputs;
+            s.subscribers.retain_mut(|(_, f)| f(&outputs));
+        }
+        self.applied.notify_all();
+    }
+
+    pub fn outputs(&self) -> Outputs {
+        self.state.lock().unwrap().outputs
+    }
+
+    // Send an event without waiting for it. Returns the event's sequence number, or
+    // None if the driver has gone.
+    pub fn send(&self, event: Event) -> Option<u64> {
+        let mut events = self.events.lock().unwrap();
+        events.0.send(event).ok()?;
+        events.1 += 1;
+        Some(events.1)
+    }
+
+    // Send an event, and wait for it to be applied. The outputs returned are from
+    // after it - though others may have changed them since, too.
+    pub fn apply(&self, event: Event) -> Option<Outputs> {
+        let seq = self.send(event)?;
+        let mut s = self.state.lock().unwrap();
+        while s.applied < seq {
+            s = self.applied.wait(s).unwrap();
+        }
+        Some(s.outputs)
+    }
+
+    // The subscriber is called straight away with the current outputs, then again
+    // every time they change, so it never misses one.
+    pub fn subscribe(&self, mut f: Subscriber) -> usize {
+        let mut s = self.state.lock().unwrap();
+        let id = s.next_id;
+        s.next_id += 1;
+        if f(&s.outputs) {
+            s.subscribers.push((id, f));
+        }
+        id
+    }
+
+    pub fn unsubscribe(&self, id: usize) {
+        self.state.lock().unwrap().subscribers.retain(|(i, _)| *i != id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Write};
+    use std::sync::mpsc::{channel, Receiver};
+    use std::time::Duration;
+
+    use crate::hub::{outbox, Hub};
+    use microwave_common::clock::VirtualClock;
+    use microwave_common::{Event, MicrowaveOps};
+
+    #[test]
+    fn hub() {
+        let clock = VirtualClock::new();
+        let hub = Hub::start(rust_microwave_simple::Microwave::new(), clock.clone());
+        let (tx, rx) = channel();
+        let id = hub.subscribe(Box::new(move |o| tx.send(*o).is_ok()));
+        assert!(!rx.recv().unwrap().magnetron);
+
+        let o = hub.apply(Event::Start).unwrap();
+        assert!(o.magnetron && o.time_remain == 30);
+        assert!(rx.recv().unwrap() == o);
+
+        clock.advance(Duration::from_secs(1));
+        assert!(rx.recv().unwrap().time_remain == 29);
+
+        hub.unsubscribe(id);
+        hub.apply(Event::Stop).unwrap();
+        assert!(rx.try_recv().is_err());
+        assert!(!hub.outputs().magnetron);
+    }
+
+    // A client that never reads, until it's told to give up.
+    struct Stuck(Receiver<()>);
+
+    impl Write for Stuck {
+        fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+            let _ = self.0.recv();
+            Err(io::ErrorKind::TimedOut.into())
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn slow_client() {
+        let (give_up, stuck) = channel();
+        let (hung_up_tx, hung_up) = channel();
+        let out = outbox(Stuck(stuck), move || hung_up_tx.send(()).unwrap());
+
+        // Nobody waits for it, however far behind it gets.
+        let hub = Hub::start(rust_microwave_simple::Microwave::new(), VirtualClock::new());
+        let q = out.clone();
+        hub.subscribe(Box::new(move |o| q.send(o.to_string()).is_ok()));
+        for t in 1..1000 {
+            assert!(hub.apply(Event::SetTime(t)).unwrap().time_remain == t);
+        }
+
+        // Once a write fails, it's hung up on and nothing more is queued.
+        give_up.send(()).unwrap();
+        hung_up.recv().unwrap();
+        assert!(out.send("OK".to_string()).is_err());
+        hub.apply(Event::Start).unwrap();
+    }
+}
@@ -0,0 +1,274 @@
+pub mod hub;
+pub mod rpc;
+pub mod server;
+
+use std::fmt;
+use std::io::{self, BufRead, Write};
+use std::str::FromStr;
+
+use microwave_common::display::Frame;
+use microwave_common::safe::SafeMicrowave;
+use microwave_common::{Event, MicrowaveOps};
+
+// Which microwave to drive. They all implement MicrowaveOps, so they should all
+// behave the same - that's rather the point of trying them side by side.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Implementation {
+    Spaghetti,
+    Simple,
+    Typed,
+    C,
+}
+
+impl FromStr for Implementation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "spaghetti" => Ok(Implementation::Spaghetti),
+            "simple" => Ok(Implementation::Simple),
+            "typed" => Ok(Implementation::Typed),
+            "c" => Ok(Implementation::C),
+            _ => Err(format!("unknown implementation '{}', expected spaghetti, simple, typed or c", s)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Command {
+    Event(Event),
+    // One second elapses, this many times.
+    Tick(usize),
+    Status,
+    Help,
+    Quit,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum CommandError {
+    Unknown(String),
+    Argument(String),
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CommandError::Unknown(c) => write!(f, "unknown command '{}', try 'help'", c),
+            CommandError::Argument(c) => write!(f, "'{}' needs a number of seconds, try 'help'", c),
+        }
+    }
+}
+
+pub const HELP: &str = "\
+commands:
+    open        open the door
+    close       close the door
+    set <secs>  set the time
+    start       press start
+    stop        press stop
+    tick [n]    let one (or n) seconds pass
+    reset       unbox a brand new microwave
+    status      show the microwave
+    help        show this
+    quit        leave";
+
+fn parse_seconds(cmd: &str, arg: Option<&str>) -> Result<usize, CommandError> {
+    arg.and_then(|a| a.parse().ok())
+        .ok_or_else(|| CommandError::Argument(cmd.to_string()))
+}
+
+// Blank lines and # comments parse to None, so that scripts can be piped in.
+pub fn parse_command(line: &str) -> Result<Option<Command>, CommandError> {
+    let mut words = line.split_whitespace();
+    let cmd = match words.next() {
+        None => return Ok(None),
+        Some(c) if c.starts_with('#') => return Ok(None),
+        Some(c) => c,
+    };
+    let arg = words.next();
+    let command = match (cmd, arg) {
+        ("open", None) => Command::Event(Event::OpenDoor),
+        ("close", None) => Command::Event(Event::CloseDoor),
+        ("set", a) => Command::Event(Event::SetTime(parse_seconds(cmd, a)?)),
+        ("start", None) => Command::Event(Event::Start),
+        ("stop", None) => Command::Event(Event::Stop),
+        ("reset", None) => Command::Event(Event::Reset),
+        ("tick", None) => Command::Tick(1),
+        ("tick", a) => Command::Tick(parse_seconds(cmd, a)?),
+        ("status", None) => Command::Status,
+        ("help", None) => Command::Help,
+        ("quit", None) | ("exit", None) => Command::Quit,
+        _ => return Err(CommandError::Unknown(line.trim().to_string())),
+    };
+    Ok(Some(command))
+}
+
+pub fn status<T: MicrowaveOps>(mw: &T) -> String {
+    format!(
+        "[{}] door: {:6} magnetron: {:3} time: {}",
+        Frame::render(mw),
+        if mw.door_open() { "open" } else { "closed" },
+        if mw.magnetron_enabled() { "on" } else { "off" },
+        mw.time_remain()
+    )
+}
+
+// Read commands until the input ends (or we're asked to quit), showing the
+// microwave after every one.
+pub fn simulate<T: MicrowaveOps, R: BufRead, W: Write>(mw: &mut T, input: R, output: W) -> io::Result<()> {
+    simulate_with(mw, input, output, |_| None)
+}
+
+// As simulate, but inside a SafeMicrowave, and saying so if it ever has to shut down.
+pub fn simulate_safe<T: MicrowaveOps, R: BufRead, W: Write>(
+    mw: &mut SafeMicrowave<T>,
+    input: R,
+    output: W,
+) -> io::Result<()> {
+    simulate_with(mw, input, output, |mw| {
+        mw.violation()
+            .map(|e| format!("⚠️  unsafe after '{}' - shut down until reset", e))
+    })
+}
+
+// warning is checked after every command, and printed whenever it changes.
+fn simulate_with<T, R, W, F>(mw: &mut T, input: R, mut output: W, warning: F) -> io::Result<()>
+where
+    T: MicrowaveOps,
+    R: BufRead,
+    W: Write,
+    F: Fn(&T) -> Option<String>,
+{
+    let mut warned = None;
+    for line in input.lines() {
+        match parse_command(&line?) {
+            Ok(None) => {}
+            Ok(Some(Command::Event(e))) => {
+                e.apply(mw);
+                writeln!(output, "{}", status(mw))?;
+            }
+            Ok(Some(Command::Tick(n))) => {
+                for _ in 0..n {
+                    mw.tick();
+                }
+                writeln!(output, "{}", status(mw))?;
+            }
+            Ok(Some(Command::Status)) => writeln!(output, "{}", status(mw))?,
+            Ok(Some(Command::Help)) => writeln!(output, "{}", HELP)?,
+            Ok(Some(Command::Quit)) => break,
+            Err(e) => writeln!(output, "error: {}", e)?,
+        }
+        let w = warning(mw);
+        if w != warned {
+            if let Some(w) = &w {
+                writeln!(output, "{}", w)?;
+            }
+            warned = w;
+        }
+        output.flush()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse_command, simulate, simulate_safe, Command, CommandError, Implementation};
+    use microwave_common::faulty::{Faults, Faulty};
+    use microwave_common::safe::SafeMicrowave;
+    use microwave_common::{Event, MicrowaveOps};
+
+    const SCRIPT: &str = "\
+# heat up some leftovers
+open
+set 90
+close
+start
+tick 5
+open
+start
+close
+start
+tick 85
+start
+tick
+stop
+stop
+";
+
+    fn run<T: MicrowaveOps>() -> String {
+        let mut out = Vec::new();
+        simulate(&mut T::new(), SCRIPT.as_bytes(), &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn parse() {
+        assert!(parse_command("set 90") == Ok(Some(Command::Event(Event::SetTime(90)))));
+        assert!(parse_command("  tick  ") == Ok(Some(Command::Tick(1))));
+        assert!(parse_command("tick 5") == Ok(Some(Command::Tick(5))));
+        assert!(parse_command("") == Ok(None));
+        assert!(parse_command("# a comment") == Ok(None));
+        assert!(parse_command("set") == Err(CommandError::Argument("set".to_string())));
+        assert!(parse_command("set ninety") == Err(CommandError::Argument("set".to_string())));
+        assert!(parse_command("open wide") == Err(CommandError::Unknown("open wide".to_string())));
+        assert!("typed".parse::<Implementation>() == Ok(Implementation::Typed));
+        assert!("c++".parse::<Implementation>().is_err());
+    }
+
+    #[test]
+    fn simulate_simple() {
+        let out = run::<rust_microwave_simple::Microwave>();
+        let lines: Vec<&str> = out.lines().collect();
+        assert!(lines.len() == 14);
+        assert!(lines[0] == "[do or] door: open   magnetron: off time: 0");
+        assert!(lines[4] == "[ 1:25] door: closed magnetron: on  time: 85");
+        assert!(lines[6] == "[do or] door: open   magnetron: off time: 85");
+        assert!(lines[9] == "[     ] door: closed magnetron: off time: 0");
+        // Start with no time is a quick start.
+        assert!(lines[10] == "[ 0:30] door: closed magnetron: on  time: 30");
+    }
+
+    #[test]
+    fn every_implementation_agrees() {
+        let simple = run::<rust_microwave_simple::Microwave>();
+        assert!(run::<rust_microwave_spaghetti::Microwave>() == simple);
+        assert!(run::<rust_microwave_typed::wrapper::TypedMicrowave>() == simple);
+        assert!(run::<c_microwave_simple::Microwave>() == simple);
+    }
+
+    #[test]
+    fn errors_and_quit() {
+        let mut out = Vec::new();
+        let mut mw = rust_microwave_simple::Microwave::new();
+        simulate(&mut mw, "bake\nquit\nstart\n".as_bytes(), &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out == "error: unknown command 'bake', try 'help'\n");
+        assert!(!mw.magnetron_enabled());
+    }
+
+    #[test]
+    fn safe_shutdown() {
+        let mut out = Vec::new();
+        // Forgets the door once running.
+        let mut mw = SafeMicrowave::wrap(Faulty::with(Faults {
+            ignores_door: true,
+            ..Faults::default()
+        }));
+        simulate_safe(&mut mw, "start\nopen\nclose\nstart\nreset\nstart\n".as_bytes(), &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert!(lines[0] == "[ 0:30] door: closed magnetron: on  time: 30");
+        assert!(lines[1] == "[do or] door: open   magnetron: off time: 30");
+        assert!(lines[2] == "⚠️  unsafe after 'open_door' - shut down until reset");
+        // Start is refused.
+        assert!(lines[4] == "[ 0:30] door: closed magnetron: off time: 30");
+        assert!(lines[6] == "[ 0:30] door: closed magnetron: on  time: 30");
+        assert!(lines.len() == 7);
+
+        // A safe microwave never says anything more than it would have.
+        let mut out = Vec::new();
+        simulate_safe(&mut SafeMicrowave::<rust_microwave_simple::Microwave>::new(), SCRIPT.as_bytes(), &mut out)
+            .unwrap();
+        assert!(String::from_utf8(out).unwrap() == run::<rust_microwave_simple::Microwave>());
+    }
+}
@@ -0,0 +1,48 @@
+use std::env;
+use std::io;
+use std::process;
+
+use microwave_common::safe::SafeMicrowave;
+use microwave_common::MicrowaveOps;
+use microwave_sim::{simulate_safe, status, Implementation};
+
+const USAGE: &str = "usage: microwave_sim [--impl spaghetti|simple|typed|c]";
+
+// We don't have to trust the implementation, so we don't.
+fn run<T: MicrowaveOps>() -> io::Result<()> {
+    let mut mw = SafeMicrowave::<T>::new();
+    println!("{}", status(&mw));
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    simulate_safe(&mut mw, stdin.lock(), stdout.lock())
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let imp = match args.as_slice() {
+        [] => Implementation::Simple,
+        [flag, name] if flag == "--impl" => match name.parse() {
+            Ok(imp) => imp,
+            Err(e) => {
+                eprintln!("{}\n{}", e, USAGE);
+                process::exit(2);
+            }
+        },
+        _ => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+
+    println!("🍲 {:?} microwave - type 'help' for commands", imp);
+    let r = match imp {
+        Implementation::Spaghetti => run::<rust_microwave_spaghetti::Microwave>(),
+        Implementation::Simple => run::<rust_microwave_simple::Microwave>(),
+        Implementation::Typed => run::<rust_microwave_typed::wrapper::TypedMicrowave>(),
+        Implementation::C => run::<c_microwave_simple::Microwave>(),
+    };
+    if let Err(e) = r {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
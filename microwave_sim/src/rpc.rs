@@ -0,0 +1,545 @@
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::Shutdown;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::Path;
+use std::sync::mpsc::channel;
+use std::sync::Arc;
+use std::thread;
+
+use serde_json::{json, Value};
+
+use microwave_common::clock::Clock;
+use microwave_common::{Event, MicrowaveOps, Outputs};
+
+use crate::hub::{outbox, Hub, Outbox, WRITE_TIMEOUT};
+
+// JSON-RPC 2.0, one message per line, over a unix socket. The methods are:
+//
+//     open_door, close_door, start, stop, reset
+//     set_time     params {"seconds": 90} or [90]
+//     get_state
+//     subscribe, unsubscribe
+//
+// Every one of them answers with the state of the microwave - after the action,
+// for the actions:
+//
+//     {"door_open": false, "magnetron": true, "time_remain": 90}
+//
+// Once subscribed, every change to the microwave (whoever made it, including the
+// clock) is sent as a notification:
+//
+//     {"jsonrpc": "2.0", "method": "transition", "params": {"from": <state>, "to": <state>}}
+//
+// Batches work too, though there's not a lot of call for them.
+
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+pub const INTERNAL_ERROR: i64 = -32603;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: &str) -> Self {
+        RpcError {
+            code,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for RpcError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ({})", self.message, self.code)
+    }
+}
+
+fn state(o: &Outputs) -> Value {
+    serde_json::to_value(o).unwrap()
+}
+
+fn no_params(params: &Value) -> Result<(), RpcError> {
+    match params {
+        Value::Null => Ok(()),
+        Value::Array(a) if a.is_empty() => Ok(()),
+        Value::Object(o) if o.is_empty() => Ok(()),
+        _ => Err(RpcError::new(INVALID_PARAMS, "takes no params")),
+    }
+}
+
+fn seconds(params: &Value) -> Result<usize, RpcError> {
+    let s = match params {
+        Value::Array(a) if a.len() == 1 => &a[0],
+        Value::Object(o) if o.len() == 1 => o.get("seconds").unwrap_or(&Value::Null),
+        _ => &Value::Null,
+    };
+    s.as_u64()
+        .map(|s| s as usize)
+        .ok_or_else(|| RpcError::new(INVALID_PARAMS, "expected {\"seconds\": <secs>}"))
+}
+
+// Which event a method is, if it is one.
+pub fn method_event(method: &str, params: &Value) -> Result<Option<Event>, RpcError> {
+    let event = match method {
+        "open_door" => Event::OpenDoor,
+        "close_door" => Event::CloseDoor,
+        "set_time" => return seconds(params).map(|s| Some(Event::SetTime(s))),
+        "start" => Event::Start,
+        "stop" => Event::Stop,
+        "reset" => Event::Reset,
+        _ => return Ok(None),
+    };
+    no_params(params).map(|_| Some(event))
+}
+
+struct Session {
+    hub: Arc<Hub>,
+    out: Outbox,
+    subscription: Option<usize>,
+}
+
+impl Session {
+    fn call(&mut self, method: &str, params: &Value) -> Result<Value, RpcError> {
+        if let Some(e) = method_event(method, params)? {
+            return self
+                .hub
+                .apply(e)
+                .map(|o| state(&o))
+                .ok_or_else(|| RpcError::new(INTERNAL_ERROR, "the microwave has gone"));
+        }
+        no_params(params)?;
+        match method {
+            "get_state" => Ok(state(&self.hub.outputs())),
+            "subscribe" => {
+                if let Some(id) = self.subscription.take() {
+                    self.hub.unsubscribe(id);
+                }
+                // The hub tells us the state straight away, which is the one to
+                // answer with - it's where the notifications start from.
+                let (tx, rx) = channel();
+                let out = self.out.clone();
+                let mut from = None;
+                let id = self.hub.subscribe(Box::new(move |o| match from.replace(*o) {
+                    None => tx.send(*o).is_ok(),
+                    Some(from) => {
+                        let n = json!({
+                            "jsonrpc": "2.0",
+                            "method": "transition",
+                            "params": {"from": state(&from), "to": state(o)},
+                        });
+                        out.send(n.to_string()).is_ok()
+                    }
+                }));
+                self.subscription = Some(id);
+                Ok(state(&rx.recv().unwrap()))
+            }
+            "unsubscribe" => {
+                if let Some(id) = self.subscription.take() {
+                    self.hub.unsubscribe(id);
+                }
+                Ok(state(&self.hub.outputs()))
+            }
+            _ => Err(RpcError::new(METHOD_NOT_FOUND, "method not found")),
+        }
+    }
+
+    // The response to one request, or None if it was a notification.
+    fn request(&mut self, request: &Value) -> Option<Value> {
+        let id = request.get("id").cloned();
+        let result = match (
+            request.get("jsonrpc").and_then(Value::as_str),
+            request.get("method").and_then(Value::as_str),
+            &id,
+        ) {
+            (Some("2.0"), Some(method), None)
+            | (Some("2.0"), Some(method), Some(Value::Null))
+            | (Some("2.0"), Some(method), Some(Value::Number(_)))
+            | (Some("2.0"), Some(method), Some(Value::String(_))) => {
+                let params = request.get("params").unwrap_or(&Value::Null);
+                let result = self.call(method, params);
+                // Notifications are done, but never answered.
+                id.as_ref()?;
+                result
+            }
+            _ => Err(RpcError::new(INVALID_REQUEST, "invalid request")),
+        };
+        Some(response(id.unwrap_or(Value::Null), result))
+    }
+
+    fn line(&mut self, line: &str) -> Option<Value> {
+        match serde_json::from_str(line) {
+            Ok(Value::Array(batch)) if !batch.is_empty() => {
+                let responses: Vec<Value> = batch.iter().filter_map(|r| self.request(r)).collect();
+                if responses.is_empty() {
+                    None
+                } else {
+                    Some(Value::Array(responses))
+                }
+            }
+            Ok(Value::Array(_)) => Some(response(Value::Null, Err(RpcError::new(INVALID_REQUEST, "empty batch")))),
+            Ok(request) => self.request(&request),
+            Err(_) => Some(response(Value::Null, Err(RpcError::new(PARSE_ERROR, "parse error")))),
+        }
+    }
+}
+
+fn response(id: Value, result: Result<Value, RpcError>) -> Value {
+    match result {
+        Ok(r) => json!({"jsonrpc": "2.0", "id": id, "result": r}),
+        Err(e) => json!({"jsonrpc": "2.0", "id": id, "error": {"code": e.code, "message": e.message}}),
+    }
+}
+
+fn handle_client(stream: UnixStream, hub: Arc<Hub>) -> io::Result<()> {
+    stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+    let reader = stream.try_clone()?;
+    let mut session = Session {
+        hub,
+        out: outbox(stream.try_clone()?, move || {
+            let _ = stream.shutdown(Shutdown::Both);
+        }),
+        subscription: None,
+    };
+
+    let r = (|| {
+        for line in BufReader::new(reader).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            if let Some(r) = session.line(&line) {
+                if session.out.send(r.to_string()).is_err() {
+                    break;
+                }
+            }
+        }
+        Ok(())
+    })();
+
+    if let Some(id) = session.subscription {
+        session.hub.unsubscribe(id);
+    }
+    r
+}
+
+// Serve one microwave to everyone who connects, ticking it from the clock. This
+// only returns if accepting a connection fails.
+pub fn serve<T, C>(listener: UnixListener, mw: T, clock: C) -> io::Result<()>
+where
+    T: MicrowaveOps + Send + 'static,
+    C: Clock + Send + 'static,
+{
+    let hub = Hub::start(mw, clock);
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let hub = hub.clone();
+        thread::spawn(move || handle_client(stream, hub));
+    }
+    Ok(())
+}
+
+#[derive(Debug)]
+pub enum ClientError {
+    Io(io::Error),
+    Rpc(RpcError),
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ClientError::Io(e) => write!(f, "{}", e),
+            ClientError::Rpc(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<io::Error> for ClientError {
+    fn from(e: io::Error) -> Self {
+        ClientError::Io(e)
+    }
+}
+
+fn invalid(reason: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, reason)
+}
+
+// Talks to serve. Notifications that turn up while waiting for an answer are kept
+// until asked for.
+pub struct Client {
+    reader: BufReader<UnixStream>,
+    writer: UnixStream,
+    next_id: u64,
+    notifications: Vec<Value>,
+}
+
+impl Client {
+    pub fn connect<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let writer = UnixStream::connect(path)?;
+        Ok(Client {
+            reader: BufReader::new(writer.try_clone()?),
+            writer,
+            next_id: 0,
+            notifications: Vec::new(),
+        })
+    }
+
+    pub fn stream(&self) -> &UnixStream {
+        &self.writer
+    }
+
+    fn read(&mut self) -> io::Result<Value> {
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        serde_json::from_str(&line).map_err(|_| invalid("not json"))
+    }
+
+    pub fn call(&mut self, method: &str, params: Value) -> Result<Value, ClientError> {
+        self.next_id += 1;
+        let id = self.next_id;
+        let request = json!({"jsonrpc": "2.0", "method": method, "params": params, "id": id});
+        writeln!(self.writer, "{}", request)?;
+        loop {
+            let mut m = self.read()?;
+            if m.get("id").is_none() {
+                self.notifications.push(m);
+                continue;
+            }
+            if m["id"] != id {
+                return Err(invalid("answer to the wrong request").into());
+            }
+            if let Some(e) = m.get("error") {
+                return Err(ClientError::Rpc(RpcError {
+                    code: e["code"].as_i64().unwrap_or(INTERNAL_ERROR),
+                    message: e["message"].as_str().unwrap_or("").to_string(),
+                }));
+            }
+            return Ok(m["result"].take());
+        }
+    }
+
+    // For the methods that answer with the state.
+    pub fn call_state(&mut self, method: &str, params: Value) -> Result<Outputs, ClientError> {
+        let r = self.call(method, params)?;
+        serde_json::from_value(r).map_err(|_| invalid("not a state").into())
+    }
+
+    pub fn apply(&mut self, event: Event) -> Result<Outputs, ClientError> {
+        match event {
+            Event::Reset => self.call_state("reset", Value::Null),
+            Event::OpenDoor => self.call_state("open_door", Value::Null),
+            Event::CloseDoor => self.call_state("close_door", Value::Null),
+            Event::SetTime(s) => self.call_state("set_time", json!({ "seconds": s })),
+            Event::Start => self.call_state("start", Value::Null),
+            Event::Stop => self.call_state("stop", Value::Null),
+            // Only the server's clock can do that.
+            Event::Tick => Err(ClientError::Rpc(RpcError::new(METHOD_NOT_FOUND, "can't tick remotely"))),
+        }
+    }
+
+    pub fn get_state(&mut self) -> Result<Outputs, ClientError> {
+        self.call_state("get_state", Value::Null)
+    }
+
+    pub fn subscribe(&mut self) -> Result<Outputs, ClientError> {
+        self.call_state("subscribe", Value::Null)
+    }
+
+    // The next notification, waiting for it if need be.
+    pub fn notification(&mut self) -> io::Result<Value> {
+        if !self.notifications.is_empty() {
+            return Ok(self.notifications.remove(0));
+        }
+        self.read()
+    }
+
+    // The next transition notification, as (from, to).
+    pub fn transition(&mut self) -> io::Result<(Outputs, Outputs)> {
+        let mut n = self.notification()?;
+        if n["method"] != "transition" {
+            return Err(invalid("not a transition"));
+        }
+        let from = serde_json::from_value(n["params"]["from"].take());
+        let to = serde_json::from_value(n["params"]["to"].take());
+        match (from, to) {
+            (Ok(from), Ok(to)) => Ok((from, to)),
+            _ => Err(invalid("not a transition")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::PathBuf;
+    use std::process;
+    use std::thread;
+    use std::time::Duration;
+
+    use serde_json::{json, Value};
+
+    use crate::rpc::{
+        method_event, serve, Client, ClientError, INVALID_PARAMS, INVALID_REQUEST, METHOD_NOT_FOUND, PARSE_ERROR,
+    };
+    use microwave_common::clock::VirtualClock;
+    use microwave_common::{Event, MicrowaveOps, Outputs};
+
+    fn outputs(door_open: bool, magnetron: bool, time_remain: usize) -> Outputs {
+        Outputs {
+            door_open,
+            magnetron,
+            time_remain,
+        }
+    }
+
+    #[test]
+    fn methods() {
+        assert!(method_event("open_door", &Value::Null) == Ok(Some(Event::OpenDoor)));
+        assert!(method_event("start", &json!([])) == Ok(Some(Event::Start)));
+        assert!(method_event("set_time", &json!({"seconds": 90})) == Ok(Some(Event::SetTime(90))));
+        assert!(method_event("set_time", &json!([90])) == Ok(Some(Event::SetTime(90))));
+        assert!(method_event("get_state", &Value::Null) == Ok(None));
+        for bad in &[json!(null), json!({"secs": 90}), json!([-1]), json!(["90"]), json!([90, 91])] {
+            assert!(method_event("set_time", bad).unwrap_err().code == INVALID_PARAMS);
+        }
+        assert!(method_event("stop", &json!([1])).unwrap_err().code == INVALID_PARAMS);
+    }
+
+    fn socket(name: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("microwave_rpc_{}_{}.sock", process::id(), name));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    fn rpc_error<T>(r: Result<T, ClientError>) -> i64 {
+        match r {
+            Err(ClientError::Rpc(e)) => e.code,
+            _ => panic!("expected an rpc error"),
+        }
+    }
+
+    #[test]
+    fn serve_microwave() {
+        let path = socket("serve");
+        let listener = UnixListener::bind(&path).unwrap();
+        let clock = VirtualClock::new();
+        let c = clock.clone();
+        thread::spawn(move || serve(listener, rust_microwave_simple::Microwave::new(), c));
+
+        let mut a = Client::connect(&path).unwrap();
+        a.stream().set_read_timeout(Some(Duration::from_secs(10))).unwrap();
+        let mut b = Client::connect(&path).unwrap();
+        b.stream().set_read_timeout(Some(Duration::from_secs(10))).unwrap();
+
+        assert!(a.get_state().unwrap() == outputs(false, false, 0));
+        assert!(b.subscribe().unwrap() == outputs(false, false, 0));
+
+        // Actions answer with the state after them.
+        assert!(a.apply(Event::SetTime(2)).unwrap() == outputs(false, false, 2));
+        assert!(a.apply(Event::Start).unwrap() == outputs(false, true, 2));
+        assert!(rpc_error(a.apply(Event::Tick)) == METHOD_NOT_FOUND);
+
+        // b was told, a wasn't as it never asked.
+        assert!(b.transition().unwrap() == (outputs(false, false, 0), outputs(false, false, 2)));
+        assert!(b.transition().unwrap() == (outputs(false, false, 2), outputs(false, true, 2)));
+
+        clock.advance(Duration::from_secs(2));
+        assert!(b.transition().unwrap() == (outputs(false, true, 2), outputs(false, true, 1)));
+        assert!(b.transition().unwrap() == (outputs(false, true, 1), outputs(false, false, 0)));
+
+        // Notifications that arrive during a call are kept for later.
+        assert!(a.subscribe().unwrap() == outputs(false, false, 0));
+        assert!(b.apply(Event::OpenDoor).unwrap() == outputs(true, false, 0));
+        assert!(b.call("unsubscribe", Value::Null).is_ok());
+        assert!(a.apply(Event::CloseDoor).unwrap() == outputs(false, false, 0));
+        assert!(a.transition().unwrap() == (outputs(false, false, 0), outputs(true, false, 0)));
+        assert!(a.transition().unwrap() == (outputs(true, false, 0), outputs(false, false, 0)));
+        assert!(b.get_state().unwrap() == outputs(false, false, 0));
+
+        assert!(rpc_error(a.call("bake", Value::Null)) == METHOD_NOT_FOUND);
+        assert!(rpc_error(a.call("set_time", json!({"seconds": "soon"}))) == INVALID_PARAMS);
+
+        // Hanging up doesn't bother anyone else.
+        drop(b);
+        assert!(a.apply(Event::Start).unwrap() == outputs(false, true, 30));
+        assert!(a.transition().unwrap().1 == outputs(false, true, 30));
+
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn raw_messages() {
+        let path = socket("raw");
+        let listener = UnixListener::bind(&path).unwrap();
+        thread::spawn(move || serve(listener, rust_microwave_simple::Microwave::new(), VirtualClock::new()));
+
+        let mut writer = UnixStream::connect(&path).unwrap();
+        writer.set_read_timeout(Some(Duration::from_secs(10))).unwrap();
+        let mut reader = BufReader::new(writer.try_clone().unwrap());
+        let mut send = |line: &str| -> Value {
+            writeln!(writer, "{}", line).unwrap();
+            let mut r = String::new();
+            reader.read_line(&mut r).unwrap();
+            serde_json::from_str(&r).unwrap()
+        };
+
+        let r = send(r#"{"jsonrpc": "2.0", "method": "set_time", "params": [5], "id": "a"}"#);
+        assert!(r == json!({"jsonrpc": "2.0", "id": "a", "result": {"door_open": false, "magnetron": false, "time_remain": 5}}));
+
+        let r = send("{nope");
+        assert!(r["error"]["code"] == PARSE_ERROR && r["id"] == Value::Null);
+        let r = send(r#"{"method": "start", "id": 1}"#);
+        assert!(r["error"]["code"] == INVALID_REQUEST && r["id"] == 1);
+        let r = send("[]");
+        assert!(r["error"]["code"] == INVALID_REQUEST);
+
+        // A notification is acted on but not answered, so the next thing we hear
+        // is the batch - which only answers the request in it.
+        let r = send(
+            r#"[{"jsonrpc": "2.0", "method": "start"},
+                {"jsonrpc": "2.0", "method": "get_state", "id": 2}]"#
+                .replace('\n', "")
+                .as_str(),
+        );
+        assert!(r.as_array().unwrap().len() == 1);
+        assert!(r[0]["id"] == 2 && r[0]["result"]["magnetron"] == true);
+
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn agrees_with_local() {
+        let path = socket("agrees");
+        let listener = UnixListener::bind(&path).unwrap();
+        thread::spawn(move || serve(listener, rust_microwave_simple::Microwave::new(), VirtualClock::new()));
+
+        let mut client = Client::connect(&path).unwrap();
+        let mut local = rust_microwave_typed::wrapper::TypedMicrowave::new();
+        let events = [
+            Event::OpenDoor,
+            Event::SetTime(90),
+            Event::Start,
+            Event::CloseDoor,
+            Event::Start,
+            Event::Stop,
+            Event::Stop,
+            Event::Start,
+            Event::SetTime(0),
+            Event::Reset,
+        ];
+        for e in events.iter() {
+            e.apply(&mut local);
+            assert!(client.apply(*e).unwrap() == Outputs::of(&local));
+        }
+    }
+}
@@ -0,0 +1,194 @@
+use std::io::{self, BufRead, BufReader};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::sync::Arc;
+use std::thread;
+
+use microwave_common::clock::Clock;
+use microwave_common::{Event, MicrowaveOps, Outputs};
+
+use crate::hub::{outbox, Hub, WRITE_TIMEOUT};
+
+// The protocol is one command per line, case doesn't matter:
+//
+//     OPEN, CLOSE, SET <secs>, START, STOP, STATUS
+//
+// Every command is answered with "OK" or "ERR <reason>", except STATUS which is
+// answered with a status line:
+//
+//     STATUS <open|closed> <on|off> <secs>
+//
+// Status lines are also sent to every client when you connect, and whenever the
+// microwave changes - whoever changed it, including the clock.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Request {
+    Event(Event),
+    Status,
+}
+
+pub fn parse_request(line: &str) -> Result<Request, String> {
+    let words: Vec<String> = line.split_whitespace().map(|w| w.to_uppercase()).collect();
+    let words: Vec<&str> = words.iter().map(|w| w.as_str()).collect();
+    match words.as_slice() {
+        ["OPEN"] => Ok(Request::Event(Event::OpenDoor)),
+        ["CLOSE"] => Ok(Request::Event(Event::CloseDoor)),
+        ["SET", t] => t
+            .parse()
+            .map(|t| Request::Event(Event::SetTime(t)))
+            .map_err(|_| format!("invalid time '{}'", t)),
+        ["START"] => Ok(Request::Event(Event::Start)),
+        ["STOP"] => Ok(Request::Event(Event::Stop)),
+        ["STATUS"] => Ok(Request::Status),
+        _ => Err(format!("unknown command '{}'", line.trim())),
+    }
+}
+
+pub fn status_line(o: &Outputs) -> String {
+    format!("STATUS {}", o)
+}
+
+fn handle_client(stream: TcpStream, hub: Arc<Hub>) -> io::Result<()> {
+    stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+    let reader = stream.try_clone()?;
+    let out = outbox(stream.try_clone()?, move || {
+        let _ = stream.shutdown(Shutdown::Both);
+    });
+    let o = out.clone();
+    let id = hub.subscribe(Box::new(move |outputs| o.send(status_line(outputs)).is_ok()));
+
+    let r = (|| {
+        for line in BufReader::new(reader).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let request = parse_request(&line);
+            let answer = match &request {
+                Ok(Request::Status) => status_line(&hub.outputs()),
+                Ok(Request::Event(_)) => "OK".to_string(),
+                Err(reason) => format!("ERR {}", reason),
+            };
+            if out.send(answer).is_err() {
+                break;
+            }
+            // Answer first, so that OK always comes before the change it causes.
+            if let Ok(Request::Event(e)) = request {
+                if hub.send(e).is_none() {
+                    break;
+                }
+            }
+        }
+        Ok(())
+    })();
+
+    hub.unsubscribe(id);
+    r
+}
+
+// Serve one microwave to everyone who connects, ticking it from the clock. This
+// only returns if accepting a connection fails.
+pub fn serve<T, C>(listener: TcpListener, mw: T, clock: C) -> io::Result<()>
+where
+    T: MicrowaveOps + Send + 'static,
+    C: Clock + Send + 'static,
+{
+    let hub = Hub::start(mw, clock);
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let hub = hub.clone();
+        thread::spawn(move || handle_client(stream, hub));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::thread;
+    use std::time::Duration;
+
+    use crate::server::{parse_request, serve, Request};
+    use microwave_common::clock::VirtualClock;
+    use microwave_common::{Event, MicrowaveOps};
+
+    #[test]
+    fn parse() {
+        assert!(parse_request("SET 90") == Ok(Request::Event(Event::SetTime(90))));
+        assert!(parse_request(" start ") == Ok(Request::Event(Event::Start)));
+        assert!(parse_request("STATUS") == Ok(Request::Status));
+        assert!(parse_request("SET") == Err("unknown command 'SET'".to_string()));
+        assert!(parse_request("SET soon") == Err("invalid time 'SOON'".to_string()));
+        assert!(parse_request("BAKE") == Err("unknown command 'BAKE'".to_string()));
+    }
+
+    struct Conn {
+        reader: BufReader<TcpStream>,
+        writer: TcpStream,
+    }
+
+    impl Conn {
+        fn new(port: u16) -> Self {
+            let writer = TcpStream::connect(("127.0.0.1", port)).unwrap();
+            writer.set_read_timeout(Some(Duration::from_secs(10))).unwrap();
+            Conn {
+                reader: BufReader::new(writer.try_clone().unwrap()),
+                writer,
+            }
+        }
+
+        fn send(&mut self, line: &str) {
+            writeln!(self.writer, "{}", line).unwrap();
+        }
+
+        fn recv(&mut self) -> String {
+            let mut line = String::new();
+            self.reader.read_line(&mut line).unwrap();
+            line.trim_end().to_string()
+        }
+    }
+
+    #[test]
+    fn serve_microwave() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let clock = VirtualClock::new();
+        let c = clock.clone();
+        thread::spawn(move || serve(listener, rust_microwave_simple::Microwave::new(), c));
+
+        let mut a = Conn::new(port);
+        assert!(a.recv() == "STATUS closed off 0");
+        let mut b = Conn::new(port);
+        assert!(b.recv() == "STATUS closed off 0");
+
+        a.send("SET 2");
+        assert!(a.recv() == "OK");
+        assert!(a.recv() == "STATUS closed off 2");
+        // Everyone hears about it.
+        assert!(b.recv() == "STATUS closed off 2");
+
+        b.send("bake");
+        assert!(b.recv() == "ERR unknown command 'bake'");
+        b.send("start");
+        assert!(b.recv() == "OK");
+        assert!(b.recv() == "STATUS closed on 2");
+        assert!(a.recv() == "STATUS closed on 2");
+
+        // Nothing changes without time passing, so nothing is sent.
+        a.send("CLOSE");
+        assert!(a.recv() == "OK");
+        a.send("STATUS");
+        assert!(a.recv() == "STATUS closed on 2");
+
+        clock.advance(Duration::from_secs(2));
+        assert!(a.recv() == "STATUS closed on 1");
+        assert!(a.recv() == "STATUS closed off 0");
+        assert!(b.recv() == "STATUS closed on 1");
+        assert!(b.recv() == "STATUS closed off 0");
+
+        // Hanging up doesn't bother anyone else.
+        drop(b);
+        a.send("OPEN");
+        assert!(a.recv() == "OK");
+        assert!(a.recv() == "STATUS open off 0");
+    }
+}
@@ -0,0 +1,240 @@
+use crate::Microwave;
+use microwave_common::{InterlockOps, MicrowaveOps};
+
+// How many ticks the switches may disagree (IE the door is moving) before we
+// decide a switch has failed.
+const INTERLOCK_FAULT_TICKS: usize = 2;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum InterlockState {
+    Open,
+    Closed,
+    // The switches disagree, and have done so for this many ticks.
+    Disagree(usize),
+    // A switch has failed. Only a reset gets us out of here.
+    Fault,
+}
+
+pub struct InterlockMicrowave {
+    mw: Microwave,
+    primary_closed: bool,
+    secondary_closed: bool,
+    state: InterlockState,
+}
+
+impl InterlockMicrowave {
+    fn switch_changed(&mut self) {
+        self.state = match (self.state, self.primary_closed, self.secondary_closed) {
+            (InterlockState::Fault, _, _) => InterlockState::Fault,
+            (_, true, true) => InterlockState::Closed,
+            (_, false, false) => InterlockState::Open,
+            (InterlockState::Disagree(n), _, _) => InterlockState::Disagree(n),
+            (_, _, _) => InterlockState::Disagree(0),
+        };
+
+        // Only when both switches agree the door is closed do we tell the
+        // microwave. Anything else is an open door.
+        if self.primary_closed && self.secondary_closed {
+            self.mw.action_close_door();
+        } else {
+            self.mw.action_open_door();
+        }
+    }
+}
+
+impl MicrowaveOps for InterlockMicrowave {
+    fn new() -> Self {
+        InterlockMicrowave {
+            mw: Microwave::new(),
+            primary_closed: true,
+            secondary_closed: true,
+            state: InterlockState::Closed,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.mw.reset();
+        self.primary_closed = true;
+        self.secondary_closed = true;
+        self.state = InterlockState::Closed;
+    }
+
+    fn tick(&mut self) {
+        self.state = match self.state {
+            InterlockState::Disagree(n) if n + 1 >= INTERLOCK_FAULT_TICKS => InterlockState::Fault,
+            InterlockState::Disagree(n) => InterlockState::Disagree(n + 1),
+            s => s,
+        };
+        self.mw.tick();
+    }
+
+    fn magnetron_enabled(&self) -> bool {
+        self.mw.magnetron_enabled()
+    }
+
+    fn door_open(&self) -> bool {
+        self.mw.door_open()
+    }
+
+    fn time_remain(&self) -> usize {
+        self.mw.time_remain()
+    }
+
+    // A working door moves both switches together.
+    fn action_open_door(&mut self) {
+        self.primary_closed = false;
+        self.secondary_closed = false;
+        self.switch_changed();
+    }
+
+    fn action_close_door(&mut self) {
+        self.primary_closed = true;
+        self.secondary_closed = true;
+        self.switch_changed();
+    }
+
+    fn action_set_time(&mut self, t: usize) {
+        self.mw.action_set_time(t);
+    }
+
+    fn action_start(&mut self) {
+        if self.state == InterlockState::Closed {
+            self.mw.action_start();
+        }
+    }
+
+    fn action_stop(&mut self) {
+        self.mw.action_stop();
+    }
+}
+
+impl InterlockOps for InterlockMicrowave {
+    fn action_primary_switch(&mut self, closed: bool) {
+        self.primary_closed = closed;
+        self.switch_changed();
+    }
+
+    fn action_secondary_switch(&mut self, closed: bool) {
+        self.secondary_closed = closed;
+        self.switch_changed();
+    }
+
+    fn interlock_fault(&self) -> bool {
+        self.state == InterlockState::Fault
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::interlock::InterlockMicrowave;
+    use microwave_common::{InterlockOps, MicrowaveOps, microwave_conformance_tests};
+
+    #[derive(Clone, Copy, Debug)]
+    enum Input {
+        Primary(bool),
+        Secondary(bool),
+        SetTime,
+        Start,
+        Stop,
+        Tick,
+    }
+
+    const INPUTS: [Input; 8] = [
+        Input::Primary(true),
+        Input::Primary(false),
+        Input::Secondary(true),
+        Input::Secondary(false),
+        Input::SetTime,
+        Input::Start,
+        Input::Stop,
+        Input::Tick,
+    ];
+
+    fn apply(mw: &mut InterlockMicrowave, i: Input) {
+        match i {
+            Input::Primary(c) => mw.action_primary_switch(c),
+            Input::Secondary(c) => mw.action_secondary_switch(c),
+            Input::SetTime => mw.action_set_time(10),
+            Input::Start => mw.action_start(),
+            Input::Stop => mw.action_stop(),
+            Input::Tick => mw.tick(),
+        }
+    }
+
+    // With both switches moving together, it's just a microwave.
+    microwave_conformance_tests!(InterlockMicrowave);
+
+    #[test]
+    fn never_runs_on_disagreement() {
+        // Walk every sequence of inputs up to this length, tracking the switch
+        // positions ourselves. Whenever they disagree, the magnetron must be off.
+        const DEPTH: u32 = 6;
+        for n in 0..(INPUTS.len() as u32).pow(DEPTH) {
+            let mut mw = InterlockMicrowave::new();
+            let mut primary = true;
+            let mut secondary = true;
+            let mut seq = n;
+            for _ in 0..DEPTH {
+                let i = INPUTS[(seq % INPUTS.len() as u32) as usize];
+                seq /= INPUTS.len() as u32;
+                match i {
+                    Input::Primary(c) => primary = c,
+                    Input::Secondary(c) => secondary = c,
+                    _ => {}
+                }
+                apply(&mut mw, i);
+                if primary != secondary {
+                    assert!(mw.door_open());
+                    assert!(!mw.magnetron_enabled());
+                }
+                if mw.magnetron_enabled() {
+                    assert!(primary && secondary);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn prolonged_disagreement_faults() {
+        let mut mw = InterlockMicrowave::new();
+        mw.action_set_time(30);
+        mw.action_start();
+        assert!(mw.magnetron_enabled());
+
+        // The secondary switch sticks closed while the door opens.
+        mw.action_primary_switch(false);
+        assert!(!mw.magnetron_enabled());
+        assert!(mw.door_open());
+        mw.tick();
+        assert!(!mw.interlock_fault());
+        mw.tick();
+        assert!(mw.interlock_fault());
+
+        // Even once the switches agree again, we refuse to start.
+        mw.action_primary_switch(true);
+        assert!(!mw.door_open());
+        mw.action_start();
+        assert!(!mw.magnetron_enabled());
+        assert!(mw.interlock_fault());
+
+        // A reset (IE a service) clears the fault.
+        mw.reset();
+        assert!(!mw.interlock_fault());
+        mw.action_start();
+        assert!(mw.magnetron_enabled());
+    }
+
+    #[test]
+    fn brief_disagreement_recovers() {
+        let mut mw = InterlockMicrowave::new();
+        // Closing the door, the switches close a moment apart.
+        mw.action_primary_switch(false);
+        mw.action_secondary_switch(false);
+        mw.action_primary_switch(true);
+        mw.tick();
+        mw.action_secondary_switch(true);
+        assert!(!mw.interlock_fault());
+        mw.action_start();
+        assert!(mw.magnetron_enabled());
+    }
+}
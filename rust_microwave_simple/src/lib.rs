@@ -1,7 +1,22 @@
+// Nothing here needs std, so this microwave can run on a microcontroller - build
+// it without default features.
+#![cfg_attr(not(feature = "std"), no_std)]
+
 use microwave_common::MicrowaveOps;
 
-#[derive(Clone, Copy)]
-enum MicrowaveState {
+pub mod interlock;
+pub mod relay;
+mod snapshot;
+pub mod thermal;
+
+use crate::thermal::{ThermalState, AMBIENT_TEMP};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum MicrowaveState {
     OpenNoTime,
     OpenTime(usize),
     ClosedNoTimeNoMtron,
@@ -9,22 +24,34 @@ enum MicrowaveState {
     ClosedTimeMtron(usize),
 }
 
-struct Microwave {
-    state: MicrowaveState
+pub struct Microwave {
+    state: MicrowaveState,
+    thermal: ThermalState,
+    cavity_temp: usize,
+}
+
+impl Microwave {
+    pub fn state(&self) -> MicrowaveState {
+        self.state
+    }
 }
 
 impl MicrowaveOps for Microwave {
     fn new() -> Self {
         Microwave {
             state: MicrowaveState::ClosedNoTimeNoMtron,
+            thermal: ThermalState::Normal,
+            cavity_temp: AMBIENT_TEMP,
         }
     }
 
+    // The cavity is as hot as it was, so the thermal model carries on.
     fn reset(&mut self) {
         self.state = MicrowaveState::ClosedNoTimeNoMtron;
     }
 
     fn tick(&mut self) {
+        let was_running = self.magnetron_enabled();
         self.state = match self.state {
             MicrowaveState::ClosedTimeMtron(mut time) => {
                 // prevent underflow
@@ -39,6 +66,8 @@ impl MicrowaveOps for Microwave {
             }
             s => s
         };
+        // The cavity heated (or cooled) during the second that just passed.
+        self.thermal_tick(was_running);
     }
 
     fn magnetron_enabled(&self) -> bool {
@@ -90,6 +119,9 @@ impl MicrowaveOps for Microwave {
     }
 
     fn action_start(&mut self) {
+        if self.thermal == ThermalState::Overheated {
+            return;
+        }
         self.state = match self.state {
             MicrowaveState::ClosedNoTimeNoMtron => MicrowaveState::ClosedTimeMtron(30),
             MicrowaveState::ClosedTimeNoMtron(t) => MicrowaveState::ClosedTimeMtron(t),
@@ -111,11 +143,42 @@ impl MicrowaveOps for Microwave {
 #[cfg(test)]
 mod tests {
     use crate::Microwave;
-    use microwave_common::{MicrowaveOps, test_microwave};
+    use microwave_common::microwave_conformance_tests;
+
+    microwave_conformance_tests!(Microwave);
 
+    #[cfg(feature = "serde")]
     #[test]
-    fn it_works() {
+    fn state_json() {
+        use crate::MicrowaveState;
+        use microwave_common::{Event, MicrowaveOps, TraceRecord};
+
+        // Through the public accessor, as a pipeline would see it.
+        let mut mw = Microwave::new();
+        mw.action_set_time(59);
+        mw.action_start();
+        assert!(serde_json::to_string(&mw.state()).unwrap() == "{\"ClosedTimeMtron\":59}");
+
+        let states = [
+            MicrowaveState::OpenNoTime,
+            MicrowaveState::OpenTime(25),
+            MicrowaveState::ClosedNoTimeNoMtron,
+            MicrowaveState::ClosedTimeNoMtron(389),
+            MicrowaveState::ClosedTimeMtron(59),
+        ];
+        for state in states.iter() {
+            let json = serde_json::to_string(state).unwrap();
+            assert!(serde_json::from_str::<MicrowaveState>(&json).unwrap() == *state);
+        }
+        assert!(serde_json::to_string(&states[4]).unwrap() == "{\"ClosedTimeMtron\":59}");
+
+        // And a trace of this microwave cooking survives the trip too.
         let mut mw = Microwave::new();
-        assert!(test_microwave(&mut mw));
+        let trace: Vec<TraceRecord> = [Event::SetTime(2), Event::Start, Event::Tick, Event::Tick]
+            .iter()
+            .map(|e| TraceRecord::step(&mut mw, *e))
+            .collect();
+        let json = serde_json::to_string(&trace).unwrap();
+        assert!(serde_json::from_str::<Vec<TraceRecord>>(&json).unwrap() == trace);
     }
 }
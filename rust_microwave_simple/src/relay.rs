@@ -0,0 +1,175 @@
+use crate::Microwave;
+use microwave_common::{MicrowaveOps, RelayFeedbackOps};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum RelayState {
+    // Command and feedback agree.
+    Ok,
+    // The magnetron was sensed on while we had it commanded off.
+    Welded,
+    // The magnetron was sensed on while the door was open. Very bad.
+    DoorOpen,
+}
+
+pub struct RelayMicrowave {
+    mw: Microwave,
+    state: RelayState,
+}
+
+impl RelayMicrowave {
+    fn check_feedback(&mut self, magnetron_sensed: bool) {
+        let before = self.state;
+        self.state = match (self.state, magnetron_sensed) {
+            (RelayState::Ok, true) if self.mw.door_open() => RelayState::DoorOpen,
+            (RelayState::Ok, true) if !self.mw.magnetron_enabled() => RelayState::Welded,
+            (s, _) => s,
+        };
+
+        if before == RelayState::Ok && self.state != RelayState::Ok {
+            // We can't trust the relay, so command everything off, once. From
+            // here on action_start refuses, which is all that's needed - stopping
+            // every tick would throw away any time set.
+            self.mw.action_stop();
+        }
+    }
+}
+
+impl MicrowaveOps for RelayMicrowave {
+    fn new() -> Self {
+        RelayMicrowave {
+            mw: Microwave::new(),
+            state: RelayState::Ok,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.mw.reset();
+        self.state = RelayState::Ok;
+    }
+
+    // Without a sensor, assume a perfect relay that does what it's told.
+    fn tick(&mut self) {
+        let sensed = self.mw.magnetron_enabled();
+        self.tick_sensed(sensed);
+    }
+
+    fn magnetron_enabled(&self) -> bool {
+        self.mw.magnetron_enabled()
+    }
+
+    fn door_open(&self) -> bool {
+        self.mw.door_open()
+    }
+
+    fn time_remain(&self) -> usize {
+        self.mw.time_remain()
+    }
+
+    fn action_open_door(&mut self) {
+        self.mw.action_open_door();
+    }
+
+    fn action_close_door(&mut self) {
+        self.mw.action_close_door();
+    }
+
+    fn action_set_time(&mut self, t: usize) {
+        self.mw.action_set_time(t);
+    }
+
+    fn action_start(&mut self) {
+        if self.state == RelayState::Ok {
+            self.mw.action_start();
+        }
+    }
+
+    fn action_stop(&mut self) {
+        self.mw.action_stop();
+    }
+}
+
+impl RelayFeedbackOps for RelayMicrowave {
+    fn tick_sensed(&mut self, magnetron_sensed: bool) {
+        // The sensor reading covers the second that just passed, so compare it
+        // to what we commanded *before* the tick moves us on.
+        self.check_feedback(magnetron_sensed);
+        self.mw.tick();
+    }
+
+    fn relay_fault(&self) -> bool {
+        self.state != RelayState::Ok
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::relay::{RelayMicrowave, RelayState};
+    use microwave_common::{MicrowaveOps, RelayFeedbackOps, microwave_conformance_tests};
+
+    // A perfect relay is just a microwave.
+    microwave_conformance_tests!(RelayMicrowave);
+
+    #[test]
+    fn welded_relay() {
+        let mut mw = RelayMicrowave::new();
+        mw.action_set_time(2);
+        mw.action_start();
+        mw.tick_sensed(true);
+        mw.tick_sensed(true);
+        assert!(!mw.magnetron_enabled());
+        assert!(!mw.relay_fault());
+        // We finished cooking, but the current is still flowing.
+        mw.tick_sensed(true);
+        assert!(mw.relay_fault());
+        assert!(mw.state == RelayState::Welded);
+
+        // Nothing will start us again until we are serviced.
+        mw.action_start();
+        assert!(!mw.magnetron_enabled());
+        mw.reset();
+        assert!(!mw.relay_fault());
+        mw.action_start();
+        assert!(mw.magnetron_enabled());
+    }
+
+    #[test]
+    fn energised_with_door_open() {
+        let mut mw = RelayMicrowave::new();
+        mw.action_start();
+        mw.tick_sensed(true);
+        mw.action_open_door();
+        mw.tick_sensed(true);
+        assert!(mw.relay_fault());
+        assert!(mw.state == RelayState::DoorOpen);
+        // The fault clears the time too, so closing the door leaves us idle.
+        mw.action_close_door();
+        mw.action_start();
+        assert!(!mw.magnetron_enabled());
+        assert!(mw.time_remain() == 0);
+    }
+
+    #[test]
+    fn faulted_keeps_time() {
+        let mut mw = RelayMicrowave::new();
+        mw.tick_sensed(true);
+        assert!(mw.relay_fault());
+        // Still faulted, and still idle, but the time set isn't lost.
+        mw.action_set_time(10);
+        mw.tick_sensed(true);
+        assert!(mw.relay_fault());
+        assert!(mw.time_remain() == 10);
+        mw.action_start();
+        assert!(!mw.magnetron_enabled());
+    }
+
+    #[test]
+    fn open_relay_is_not_a_fault() {
+        // The magnetron failing to turn *on* is annoying (cold food), but safe.
+        let mut mw = RelayMicrowave::new();
+        mw.action_start();
+        mw.tick_sensed(false);
+        assert!(!mw.relay_fault());
+        assert!(mw.magnetron_enabled());
+        assert!(mw.time_remain() == 29);
+    }
+}
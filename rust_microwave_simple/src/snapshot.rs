@@ -0,0 +1,142 @@
+use crate::thermal::{ThermalState, AMBIENT_TEMP};
+use crate::{Microwave, MicrowaveState};
+use microwave_common::snapshot::*;
+
+// The thermal model is persisted too. We can't know how long the power was out
+// for, so we assume it wasn't long - an overheated microwave comes back locked out,
+// and cools from where it was.
+impl SnapshotOps for Microwave {
+    fn snapshot(&self) -> [u8; SNAPSHOT_LEN] {
+        let thermal = ThermalSnapshot {
+            overheated: self.thermal == ThermalState::Overheated,
+            cavity_temp: self.cavity_temp.min(u16::MAX as usize) as u16,
+        };
+        match self.state {
+            MicrowaveState::OpenNoTime => encode_snapshot(TAG_OPEN_NOTIME, 0, thermal),
+            MicrowaveState::OpenTime(t) => encode_snapshot(TAG_OPEN_TIME, t, thermal),
+            MicrowaveState::ClosedNoTimeNoMtron => encode_snapshot(TAG_CLOSED_NOTIME_NOMTRON, 0, thermal),
+            MicrowaveState::ClosedTimeNoMtron(t) => encode_snapshot(TAG_CLOSED_TIME_NOMTRON, t, thermal),
+            MicrowaveState::ClosedTimeMtron(t) => encode_snapshot(TAG_CLOSED_TIME_MTRON, t, thermal),
+        }
+    }
+
+    fn restore(snapshot: &[u8]) -> Result<Self, SnapshotError> {
+        let (tag, t, thermal) = decode_snapshot(snapshot)?;
+        let state = match tag {
+            TAG_OPEN_NOTIME => MicrowaveState::OpenNoTime,
+            TAG_OPEN_TIME => MicrowaveState::OpenTime(t),
+            TAG_CLOSED_NOTIME_NOMTRON => MicrowaveState::ClosedNoTimeNoMtron,
+            TAG_CLOSED_TIME_NOMTRON => MicrowaveState::ClosedTimeNoMtron(t),
+            // We were running when the power went - come back stopped.
+            TAG_CLOSED_TIME_MTRON => MicrowaveState::ClosedTimeNoMtron(t),
+            _ => return Err(SnapshotError::Corrupt),
+        };
+        Ok(Microwave {
+            state,
+            thermal: if thermal.overheated {
+                ThermalState::Overheated
+            } else {
+                ThermalState::Normal
+            },
+            // A microwave without a thermal model never measured it.
+            cavity_temp: (thermal.cavity_temp as usize).max(AMBIENT_TEMP),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::thermal::{AMBIENT_TEMP, OVERHEAT_TEMP};
+    use crate::{Microwave, MicrowaveState};
+    use microwave_common::{MicrowaveOps, ThermalOps};
+    use microwave_common::snapshot::{SnapshotError, SnapshotOps};
+
+    fn outputs(mw: &Microwave) -> (bool, bool, usize) {
+        (mw.door_open(), mw.magnetron_enabled(), mw.time_remain())
+    }
+
+    #[test]
+    fn round_trip() {
+        let states = [
+            MicrowaveState::OpenNoTime,
+            MicrowaveState::OpenTime(25),
+            MicrowaveState::ClosedNoTimeNoMtron,
+            MicrowaveState::ClosedTimeNoMtron(389),
+            MicrowaveState::ClosedTimeMtron(59),
+        ];
+        for state in states.iter() {
+            let mut mw = Microwave::new();
+            mw.state = *state;
+            let restored = Microwave::restore(&mw.snapshot()).unwrap();
+
+            if mw.magnetron_enabled() {
+                // Running comes back stopped, time preserved.
+                assert!(outputs(&restored) == (false, false, mw.time_remain()));
+            } else {
+                assert!(outputs(&restored) == outputs(&mw));
+            }
+            // And a snapshot of a restored microwave restores the same again.
+            let again = Microwave::restore(&restored.snapshot()).unwrap();
+            assert!(outputs(&again) == outputs(&restored));
+        }
+    }
+
+    #[test]
+    fn encoding() {
+        let mut mw = Microwave::new();
+        mw.action_start();
+        mw.action_start();
+        assert!(mw.snapshot() == [2, 4, 60, 0, 0, 0, 0, 0, 0, 0, 0, 20, 0]);
+        mw.action_open_door();
+        assert!(mw.snapshot() == [2, 1, 60, 0, 0, 0, 0, 0, 0, 0, 0, 20, 0]);
+    }
+
+    #[test]
+    fn overheated_stays_locked_out() {
+        let mut mw = Microwave::new();
+        mw.action_set_time(200);
+        mw.action_start();
+        while mw.magnetron_enabled() {
+            mw.tick();
+        }
+        assert!(mw.overheated());
+        assert!(mw.snapshot()[10..] == [1, OVERHEAT_TEMP as u8, 0]);
+
+        let mut restored = Microwave::restore(&mw.snapshot()).unwrap();
+        assert!(restored.overheated());
+        assert!(restored.cavity_temp() == OVERHEAT_TEMP);
+        restored.action_start();
+        assert!(!restored.magnetron_enabled());
+
+        // One from a microwave with no thermal model comes back at room temperature.
+        let restored = Microwave::restore(&[2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]).unwrap();
+        assert!(!restored.overheated());
+        assert!(restored.cavity_temp() == AMBIENT_TEMP);
+    }
+
+    #[test]
+    fn restored_microwave_works() {
+        let mut mw = Microwave::new();
+        mw.action_set_time(3);
+        mw.action_start();
+        mw.tick();
+        let mut mw = Microwave::restore(&mw.snapshot()).unwrap();
+        assert!(outputs(&mw) == (false, false, 2));
+        mw.action_start();
+        mw.tick();
+        mw.tick();
+        assert!(outputs(&mw) == (false, false, 0));
+    }
+
+    #[test]
+    fn rejects_bad_snapshots() {
+        assert!(Microwave::restore(&[]).err() == Some(SnapshotError::Length));
+        assert!(Microwave::restore(&[2, 0, 0]).err() == Some(SnapshotError::Length));
+        // A version 1 snapshot, from before the thermal model was saved.
+        assert!(Microwave::restore(&[1, 0, 0, 0, 0, 0, 0, 0, 0, 0]).err() == Some(SnapshotError::Version));
+        assert!(Microwave::restore(&[2, 5, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]).err() == Some(SnapshotError::Corrupt));
+        // No time states can't have a time.
+        assert!(Microwave::restore(&[2, 0, 9, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]).err() == Some(SnapshotError::Corrupt));
+        assert!(Microwave::restore(&[2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0]).err() == Some(SnapshotError::Corrupt));
+    }
+}
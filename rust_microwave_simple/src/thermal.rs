@@ -0,0 +1,143 @@
+use crate::{Microwave, MicrowaveState};
+use microwave_common::ThermalOps;
+
+// All temperatures are in degrees celsius.
+pub const AMBIENT_TEMP: usize = 20;
+// Above this, the thermal cut-out trips and we stop the magnetron.
+pub const OVERHEAT_TEMP: usize = 140;
+// Once tripped, we have to cool down to this before we'll start again.
+pub const COOLED_TEMP: usize = 100;
+// Degrees gained per second with the magnetron on, and lost with it off.
+pub const HEAT_RATE: usize = 1;
+pub const COOL_RATE: usize = 1;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ThermalState {
+    Normal,
+    Overheated,
+}
+
+impl Microwave {
+    pub fn thermal_state(&self) -> ThermalState {
+        self.thermal
+    }
+
+    pub(crate) fn thermal_tick(&mut self, was_running: bool) {
+        if was_running {
+            self.cavity_temp += HEAT_RATE;
+        } else if self.cavity_temp > AMBIENT_TEMP {
+            self.cavity_temp = (self.cavity_temp - COOL_RATE).max(AMBIENT_TEMP);
+        }
+
+        self.thermal = match self.thermal {
+            ThermalState::Normal if self.cavity_temp >= OVERHEAT_TEMP => ThermalState::Overheated,
+            ThermalState::Overheated if self.cavity_temp <= COOLED_TEMP => ThermalState::Normal,
+            s => s,
+        };
+
+        // The cut-out stops us, but like the stop button, leaves the time so you can
+        // resume once it's cooled.
+        if self.thermal == ThermalState::Overheated {
+            self.state = match self.state {
+                MicrowaveState::ClosedTimeMtron(t) => MicrowaveState::ClosedTimeNoMtron(t),
+                s => s,
+            };
+        }
+    }
+}
+
+impl ThermalOps for Microwave {
+    fn cavity_temp(&self) -> usize {
+        self.cavity_temp
+    }
+
+    fn overheated(&self) -> bool {
+        self.thermal == ThermalState::Overheated
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Microwave;
+    use crate::thermal::{ThermalState, AMBIENT_TEMP, COOLED_TEMP, OVERHEAT_TEMP};
+    use microwave_common::{MicrowaveOps, ThermalOps};
+
+    fn cook(mw: &mut Microwave, t: usize) {
+        mw.action_set_time(t);
+        mw.action_start();
+        while mw.magnetron_enabled() {
+            mw.tick();
+        }
+    }
+
+    #[test]
+    fn heats_and_cools() {
+        let mut mw = Microwave::new();
+        assert!(mw.cavity_temp() == AMBIENT_TEMP);
+        cook(&mut mw, 30);
+        assert!(mw.cavity_temp() == AMBIENT_TEMP + 30);
+        assert!(!mw.overheated());
+        for _ in 0..100 {
+            mw.tick();
+        }
+        assert!(mw.cavity_temp() == AMBIENT_TEMP);
+    }
+
+    #[test]
+    fn back_to_back_cooks_cut_out() {
+        let mut mw = Microwave::new();
+        // The first long cook is fine ...
+        cook(&mut mw, 90);
+        assert!(!mw.overheated());
+        assert!(mw.time_remain() == 0);
+
+        // ... but going straight into another trips the cut-out part way.
+        cook(&mut mw, 90);
+        assert!(mw.overheated());
+        assert!(mw.cavity_temp() == OVERHEAT_TEMP);
+        assert!(!mw.magnetron_enabled());
+        assert!(mw.time_remain() == 60);
+
+        // Start is refused until we cool down.
+        mw.action_start();
+        assert!(!mw.magnetron_enabled());
+        while mw.overheated() {
+            assert!(!mw.magnetron_enabled());
+            mw.tick();
+            mw.action_start();
+        }
+        assert!(mw.cavity_temp() == COOLED_TEMP);
+
+        // And we resume with the remaining time.
+        assert!(mw.magnetron_enabled());
+        assert!(mw.time_remain() == 60);
+    }
+
+    #[test]
+    fn overheated_door_still_works() {
+        let mut mw = Microwave::new();
+        cook(&mut mw, 200);
+        assert!(mw.overheated());
+        mw.action_open_door();
+        assert!(mw.door_open());
+        mw.action_close_door();
+        mw.action_start();
+        assert!(!mw.magnetron_enabled());
+    }
+
+    #[test]
+    fn reset_doesnt_cool() {
+        let mut mw = Microwave::new();
+        cook(&mut mw, 200);
+        assert!(mw.thermal_state() == ThermalState::Overheated);
+        // Resetting the controller doesn't make the cavity any cooler, so it's
+        // still locked out.
+        mw.reset();
+        assert!(mw.overheated());
+        assert!(mw.cavity_temp() == OVERHEAT_TEMP);
+        mw.action_start();
+        assert!(!mw.magnetron_enabled());
+        // Though the rest of the machine is as good as new.
+        assert!(mw.time_remain() == 0);
+    }
+}
@@ -1,6 +1,6 @@
 use microwave_common::MicrowaveOps;
 
-struct Microwave {
+pub struct Microwave {
     door_open: bool,
     // This is an excellent example of why you always use
     // positive langage in booleans, rather than negatives :)
@@ -99,11 +99,7 @@ impl MicrowaveOps for Microwave {
 #[cfg(test)]
 mod tests {
     use crate::Microwave;
-    use microwave_common::{MicrowaveOps, test_microwave};
+    use microwave_common::microwave_conformance_tests;
 
-    #[test]
-    fn it_works() {
-        let mut mw = Microwave::new();
-        assert!(test_microwave(&mut mw));
-    }
+    microwave_conformance_tests!(Microwave);
 }
@@ -1,4 +1,6 @@
 
+pub mod wrapper;
+
 #[derive(Debug)]
 struct OpenNoTime;
 #[derive(Debug)]
@@ -163,7 +165,8 @@ impl Microwave<ClosedTimeMtron> {
     }
 
     fn tick(self) -> Result<Microwave<ClosedTimeMtron>, Microwave<ClosedNoTimeNoMtron>> {
-        if self.state.t == 1 {
+        // <= rather than == so that a time of 0 (IE set_time(0)) can't underflow.
+        if self.state.t <= 1 {
             Err(Microwave {
                 state: ClosedNoTimeNoMtron
             })
@@ -311,4 +314,16 @@ mod tests {
         let mut mw: Microwave<OpenTime> = mw.action_open_door();
         assert_mw!(mw, true, false, 35);
     }
+    #[test]
+    fn test_7() {
+        // A time of 0 is done on the first tick, rather than counting down from
+        // usize::MAX (or panicking in a debug build).
+        let mut mw: Microwave<ClosedNoTimeNoMtron> = Microwave::new();
+        let mut mw: Microwave<ClosedTimeNoMtron> = mw.action_set_time(0);
+        assert_mw!(mw, false, false, 0);
+        let mut mw: Microwave<ClosedTimeMtron> = mw.action_start();
+        assert_mw!(mw, false, true, 0);
+        let mut mw: Microwave<ClosedNoTimeNoMtron> = mw.tick().unwrap_err();
+        assert_mw!(mw, false, false, 0);
+    }
 }
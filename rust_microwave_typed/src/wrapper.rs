@@ -0,0 +1,132 @@
+use crate::{ClosedNoTimeNoMtron, ClosedTimeMtron, ClosedTimeNoMtron, Microwave, OpenNoTime, OpenTime};
+use microwave_common::MicrowaveOps;
+
+// The typed microwave is checked at compile time, so it can't react to events that
+// only arrive at run time. To drive it from a keyboard (or the test suite) we hold
+// whichever state it's in right now, and match on it. That makes it a run time
+// state machine again, but every transition still has to be one the types allow -
+// anything else leaves it where it is.
+enum State {
+    OpenNoTime(Microwave<OpenNoTime>),
+    OpenTime(Microwave<OpenTime>),
+    ClosedNoTimeNoMtron(Microwave<ClosedNoTimeNoMtron>),
+    ClosedTimeNoMtron(Microwave<ClosedTimeNoMtron>),
+    ClosedTimeMtron(Microwave<ClosedTimeMtron>),
+}
+
+pub struct TypedMicrowave {
+    state: State,
+}
+
+impl TypedMicrowave {
+    // Take the current state out so that we can consume it, and put the next one back.
+    fn transition<F: FnOnce(State) -> State>(&mut self, f: F) {
+        let state = std::mem::replace(&mut self.state, State::ClosedNoTimeNoMtron(Microwave::new()));
+        self.state = f(state);
+    }
+}
+
+impl MicrowaveOps for TypedMicrowave {
+    fn new() -> Self {
+        TypedMicrowave {
+            state: State::ClosedNoTimeNoMtron(Microwave::new()),
+        }
+    }
+
+    fn reset(&mut self) {
+        self.state = State::ClosedNoTimeNoMtron(Microwave::new());
+    }
+
+    fn tick(&mut self) {
+        self.transition(|s| match s {
+            State::ClosedTimeMtron(m) => match m.tick() {
+                Ok(m) => State::ClosedTimeMtron(m),
+                Err(m) => State::ClosedNoTimeNoMtron(m),
+            },
+            s => s,
+        })
+    }
+
+    fn magnetron_enabled(&self) -> bool {
+        match &self.state {
+            State::OpenNoTime(m) => m.magnetron_enabled(),
+            State::OpenTime(m) => m.magnetron_enabled(),
+            State::ClosedNoTimeNoMtron(m) => m.magnetron_enabled(),
+            State::ClosedTimeNoMtron(m) => m.magnetron_enabled(),
+            State::ClosedTimeMtron(m) => m.magnetron_enabled(),
+        }
+    }
+
+    fn door_open(&self) -> bool {
+        match &self.state {
+            State::OpenNoTime(m) => m.door_open(),
+            State::OpenTime(m) => m.door_open(),
+            State::ClosedNoTimeNoMtron(m) => m.door_open(),
+            State::ClosedTimeNoMtron(m) => m.door_open(),
+            State::ClosedTimeMtron(m) => m.door_open(),
+        }
+    }
+
+    fn time_remain(&self) -> usize {
+        match &self.state {
+            State::OpenNoTime(m) => m.time_remain(),
+            State::OpenTime(m) => m.time_remain(),
+            State::ClosedNoTimeNoMtron(m) => m.time_remain(),
+            State::ClosedTimeNoMtron(m) => m.time_remain(),
+            State::ClosedTimeMtron(m) => m.time_remain(),
+        }
+    }
+
+    fn action_open_door(&mut self) {
+        self.transition(|s| match s {
+            State::ClosedNoTimeNoMtron(m) => State::OpenNoTime(m.action_open_door()),
+            State::ClosedTimeNoMtron(m) => State::OpenTime(m.action_open_door()),
+            State::ClosedTimeMtron(m) => State::OpenTime(m.action_open_door()),
+            s => s,
+        })
+    }
+
+    fn action_close_door(&mut self) {
+        self.transition(|s| match s {
+            State::OpenNoTime(m) => State::ClosedNoTimeNoMtron(m.action_close_door()),
+            State::OpenTime(m) => State::ClosedTimeNoMtron(m.action_close_door()),
+            s => s,
+        })
+    }
+
+    fn action_set_time(&mut self, t: usize) {
+        self.transition(|s| match s {
+            State::OpenNoTime(mut m) => State::OpenTime(m.action_set_time(t)),
+            State::OpenTime(m) => State::OpenTime(m.action_set_time(t)),
+            State::ClosedNoTimeNoMtron(m) => State::ClosedTimeNoMtron(m.action_set_time(t)),
+            State::ClosedTimeNoMtron(m) => State::ClosedTimeNoMtron(m.action_set_time(t)),
+            s => s,
+        })
+    }
+
+    fn action_start(&mut self) {
+        self.transition(|s| match s {
+            State::ClosedNoTimeNoMtron(m) => State::ClosedTimeMtron(m.action_start()),
+            State::ClosedTimeNoMtron(m) => State::ClosedTimeMtron(m.action_start()),
+            State::ClosedTimeMtron(m) => State::ClosedTimeMtron(m.action_start()),
+            s => s,
+        })
+    }
+
+    fn action_stop(&mut self) {
+        self.transition(|s| match s {
+            State::OpenTime(m) => State::OpenNoTime(m.action_stop()),
+            State::ClosedTimeNoMtron(m) => State::ClosedNoTimeNoMtron(m.action_stop()),
+            State::ClosedTimeMtron(m) => State::ClosedTimeNoMtron(m.action_stop()),
+            s => s,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::wrapper::TypedMicrowave;
+    use microwave_common::microwave_conformance_tests;
+
+    microwave_conformance_tests!(TypedMicrowave);
+}